@@ -0,0 +1,193 @@
+// Reprints lines with the tokens that made up their calibration value marked,
+// in colour on a terminal and with brackets otherwise:
+//
+//   digits    green   [3]
+//   words     cyan    {two}
+//   overlaps  magenta (t)
+//
+// A span is an overlap when it was picked from both ends, like the t shared by
+// "eightwo" or a line whose only digit is both its first and last.
+
+use std::fmt::Write;
+
+use crate::{chosen_tokens, chosen_value, CalibrationMode, Chosen, Patterns};
+
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Digit,
+    Word,
+    Overlap,
+}
+
+impl Style {
+    fn colour(self) -> &'static str {
+        match self {
+            Style::Digit => "\x1b[1;32m",
+            Style::Word => "\x1b[1;36m",
+            Style::Overlap => "\x1b[1;35m",
+        }
+    }
+
+    fn brackets(self) -> (char, char) {
+        match self {
+            Style::Digit => ('[', ']'),
+            Style::Word => ('{', '}'),
+            Style::Overlap => ('(', ')'),
+        }
+    }
+}
+
+// The byte ranges of the chosen tokens, for highlight_line
+fn spans(chosen: &[Chosen<'_>]) -> Vec<(usize, usize)> {
+    chosen.iter().map(|c| (c.start, c.end)).collect()
+}
+
+pub fn highlight_line(line: &str, spans: &[(usize, usize)], colour: bool) -> String {
+    let mut output = String::with_capacity(line.len() * 2);
+    // tracking which spans cover a char, not just its style, keeps two touching
+    // tokens like the "1" and "2" of "12" from merging into one marker
+    let mut current: (Vec<(usize, usize)>, Option<Style>) = (vec![], None);
+
+    for (i, c) in line.char_indices() {
+        let covering = covering_spans(spans, i);
+
+        if covering != current.0 {
+            if let Some(previous) = current.1 {
+                output.push_str(&close(previous, colour));
+            }
+            let style = style_of(line, &covering);
+            if let Some(next) = style {
+                output.push_str(&open(next, colour));
+            }
+            current = (covering, style);
+        }
+
+        output.push(c);
+    }
+
+    if let Some(previous) = current.1 {
+        output.push_str(&close(previous, colour));
+    }
+
+    output
+}
+
+pub fn highlight_input(
+    input: &str,
+    mode: CalibrationMode,
+    patterns: &Patterns,
+    colour: bool,
+) -> String {
+    input
+        .split('\n')
+        .filter(|s| !s.is_empty())
+        .fold(String::new(), |mut output, line| {
+            let chosen = chosen_tokens(line, mode, patterns);
+            let value = chosen_value(&chosen, mode).map_or("none".to_string(), |v| v.to_string());
+
+            writeln!(
+                output,
+                "{} -> {value}",
+                highlight_line(line, &spans(&chosen), colour)
+            )
+            .unwrap();
+            output
+        })
+}
+
+fn covering_spans(spans: &[(usize, usize)], i: usize) -> Vec<(usize, usize)> {
+    spans
+        .iter()
+        .filter(|(start, end)| (*start..*end).contains(&i))
+        .copied()
+        .collect()
+}
+
+fn style_of(line: &str, covering: &[(usize, usize)]) -> Option<Style> {
+    match covering {
+        [] => None,
+        [(start, end)] if line[*start..*end].bytes().all(|b| b.is_ascii_digit()) => {
+            Some(Style::Digit)
+        }
+        [_] => Some(Style::Word),
+        _ => Some(Style::Overlap),
+    }
+}
+
+fn open(style: Style, colour: bool) -> String {
+    match colour {
+        true => style.colour().to_string(),
+        false => style.brackets().0.to_string(),
+    }
+}
+
+fn close(style: Style, colour: bool) -> String {
+    match colour {
+        true => RESET.to_string(),
+        false => style.brackets().1.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brackets(line: &str, mode: CalibrationMode) -> String {
+        let chosen = chosen_tokens(line, mode, &Patterns::new());
+        highlight_line(line, &spans(&chosen), false)
+    }
+
+    #[test]
+    fn digit_brackets() {
+        assert_eq!(
+            brackets("a1b2c3d", CalibrationMode::Digits(1)),
+            "a[1]b2c[3]d"
+        );
+        assert_eq!(brackets("ab7cd", CalibrationMode::Digits(1)), "ab(7)cd");
+        assert_eq!(brackets("1234", CalibrationMode::Digits(2)), "[1][2][3][4]");
+        assert_eq!(
+            brackets("no digits", CalibrationMode::Digits(1)),
+            "no digits"
+        );
+    }
+
+    #[test]
+    fn word_brackets() {
+        let words = CalibrationMode::Words { roman: false };
+
+        assert_eq!(brackets("xtwone3four", words), "x{two}ne3{four}");
+        assert_eq!(brackets("eightwo", words), "{eigh}(t){wo}");
+        assert_eq!(brackets("9abcnine", words), "[9]abc{nine}");
+        assert_eq!(brackets("forty-two", words), "(forty-two)");
+    }
+
+    #[test]
+    fn number_brackets() {
+        assert_eq!(brackets("12ab345", CalibrationMode::Numbers), "[12]ab[345]");
+    }
+
+    #[test]
+    fn colour_codes() {
+        let words = CalibrationMode::Words { roman: false };
+        let chosen = chosen_tokens("1two", words, &Patterns::new());
+
+        assert_eq!(
+            highlight_line("1two", &spans(&chosen), true),
+            "\x1b[1;32m1\x1b[0m\x1b[1;36mtwo\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn whole_input() {
+        let output = highlight_input(
+            "a1b\n\nc2d3\n",
+            CalibrationMode::Digits(1),
+            &Patterns::new(),
+            false,
+        );
+
+        assert_eq!(output, "a(1)b -> 11\nc[2]d[3] -> 23\n");
+    }
+}
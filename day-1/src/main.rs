@@ -1,33 +1,242 @@
+use std::borrow::Cow;
 use std::num::ParseIntError;
 
 use regex::Regex;
 
+use std::io::IsTerminal;
+
+use highlight::highlight_input;
+use number_words::{find_numbers, first_and_last};
+use parallel::{map_input, par_sum_calibration};
+
+mod highlight;
+mod number_words;
+mod parallel;
+
+// How the digit tokens found in a line are combined into its calibration value
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CalibrationMode {
+    // the first k and last k digit tokens, so the classic puzzle is Digits(1)
+    Digits(usize),
+    // the first and last runs of digits, read as whole numbers
+    Numbers,
+    // the first and last whole numbers, written in digits or words like
+    // "forty-two", and optionally as Roman numerals
+    Words { roman: bool },
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    mode: CalibrationMode,
+    // whether --roman was given, in whichever order it came with --words
+    roman: bool,
+    // a file to memory map instead of the bundled input.txt
+    input_path: Option<String>,
+    parallel: bool,
+    highlight: bool,
+}
+
+// A token a line's calibration value was built from: its byte range in the line
+// and the digits it stands for
+#[derive(Debug, PartialEq)]
+struct Chosen<'a> {
+    start: usize,
+    end: usize,
+    digits: Cow<'a, str>,
+}
+
+struct Patterns {
+    digit: Regex,
+    number: Regex,
+}
+
+impl Patterns {
+    fn new() -> Self {
+        Patterns {
+            digit: Regex::new(r"\d").unwrap(),
+            number: Regex::new(r"\d+").unwrap(),
+        }
+    }
+}
+
 fn main() {
-    
+    let options = parse_args(std::env::args().skip(1));
+    let patterns = Patterns::new();
+    // let word_re = Regex::new(r"\d|oneight|twone|threeight|fiveight|sevenine|eightwo|eighthree|nineight|one|two|three|four|five|six|sever|eight|nine").unwrap();
+
+    let mapped = options.input_path.as_deref().map(map_input);
+    let input = match &mapped {
+        Some(mmap) => std::str::from_utf8(mmap).expect("the input file isn't valid UTF-8"),
+        None => include_str!("../input.txt"),
+    };
+
+    if options.highlight {
+        let colour = std::io::stdout().is_terminal();
+        print!(
+            "{}",
+            highlight_input(input, options.mode, &patterns, colour)
+        );
+    }
+
+    let sum = if options.parallel {
+        par_sum_calibration(input, options.mode, &patterns)
+    } else {
+        sum_calibration(input, options.mode, &patterns)
+    };
+
+    match sum {
+        Some(sum) => println!("The sum of all the valid lines' numbers is: {sum}"),
+        None => println!("The sum of all the valid lines' numbers overflowed a u128"),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        mode: CalibrationMode::Digits(1),
+        roman: false,
+        input_path: None,
+        parallel: false,
+        highlight: false,
+    };
+    let mut mode = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--numbers" => mode = Some(CalibrationMode::Numbers),
+            "--words" => mode = Some(CalibrationMode::Words { roman: false }),
+            "--roman" => options.roman = true,
+            "--digits" => {
+                let k = args
+                    .next()
+                    .and_then(|k| k.parse::<usize>().ok())
+                    .filter(|k| *k > 0)
+                    .expect("--digits needs a positive number of digits to take from each end");
+                mode = Some(CalibrationMode::Digits(k));
+            }
+            "--input" => {
+                options.input_path = Some(args.next().expect("--input needs a file path"));
+            }
+            "--parallel" => options.parallel = true,
+            "--highlight" => options.highlight = true,
+            other => panic!(
+                "unknown argument {other}, expected --digits <k>, --numbers, --words [--roman], --input <path>, --parallel or --highlight"
+            ),
+        }
+    }
+
+    // --roman on its own still means --words --roman
+    options.mode = match (mode, options.roman) {
+        (None | Some(CalibrationMode::Words { .. }), true) => {
+            CalibrationMode::Words { roman: true }
+        }
+        (Some(_), true) => panic!("--roman only goes with --words"),
+        (mode, false) => mode.unwrap_or(CalibrationMode::Digits(1)),
+    };
+
+    options
+}
+
+fn sum_calibration(input: &str, mode: CalibrationMode, patterns: &Patterns) -> Option<u128> {
     let content_newlines = input.split('\n').filter(|s| !s.is_empty());
 
-    let digit_re = Regex::new(r"\d").unwrap();
-    // let word_re = Regex::new(r"\d|oneight|twone|threeight|fiveight|sevenine|eightwo|eighthree|nineight|one|two|three|four|five|six|sever|eight|nine").unwrap();
+    checked_sum(content_newlines.filter_map(|line| calibration_value(line, mode, patterns)))
+}
+
+fn calibration_value(line: &str, mode: CalibrationMode, patterns: &Patterns) -> Option<u128> {
+    chosen_value(&chosen_tokens(line, mode, patterns), mode)
+}
 
-    let digit_tuples = content_newlines
-        .clone()
-        .filter_map(|line| problem_one(line, &digit_re))
-        .filter_map(parse_str_tuple)
-        .filter_map(|(first, last)| format!("{first}{last}").parse::<i32>().ok());
+// The tokens a line's calibration value is built from, with the leading tokens
+// first and any token picked from both ends listed twice. Both the value and the
+// highlighting come from these, so they can't pick different tokens.
+fn chosen_tokens<'a>(line: &'a str, mode: CalibrationMode, patterns: &Patterns) -> Vec<Chosen<'a>> {
+    let chosen = |m: &regex::Match<'a>| Chosen {
+        start: m.start(),
+        end: m.end(),
+        digits: Cow::Borrowed(m.as_str()),
+    };
 
-    println!(
-        "The sum of all the valid lines' numbers is: {}",
-        digit_tuples.sum::<i32>()
-    );
+    match mode {
+        CalibrationMode::Digits(k) => {
+            let matches: Vec<regex::Match<'_>> = patterns.digit.find_iter(line).collect();
+            if k == 0 || matches.len() < k {
+                return vec![];
+            }
+
+            matches[..k]
+                .iter()
+                .chain(&matches[matches.len() - k..])
+                .map(chosen)
+                .collect()
+        }
+        CalibrationMode::Numbers => {
+            let matches: Vec<regex::Match<'_>> = patterns.number.find_iter(line).collect();
+            match (matches.first(), matches.last()) {
+                (Some(first), Some(last)) => vec![chosen(first), chosen(last)],
+                (_, _) => vec![],
+            }
+        }
+        CalibrationMode::Words { roman } => {
+            let matches = find_numbers(line, roman);
+            first_and_last(&matches).map_or(vec![], |(first, last)| {
+                [first, last]
+                    .into_iter()
+                    .map(|m| Chosen {
+                        start: m.start,
+                        end: m.end,
+                        digits: Cow::Owned(m.decimal.clone()),
+                    })
+                    .collect()
+            })
+        }
+    }
 }
 
-fn parse_str_tuple(str_tuple: (&str, &str)) -> Option<(i32, i32)> {
-    match (parse_digit_word(str_tuple.0), parse_digit_word(str_tuple.1)) {
-        (Ok(first), Ok(last)) => Some((first, last)),
+fn chosen_value(chosen: &[Chosen<'_>], mode: CalibrationMode) -> Option<u128> {
+    let tokens: Vec<&str> = chosen.iter().map(|c| c.digits.as_ref()).collect();
+
+    match (mode, &tokens[..]) {
+        // chosen_tokens already picked the first k and last k, so this takes all of them
+        (CalibrationMode::Digits(k), _) => digits_value(&tokens, k),
+        (_, [first, last]) => numbers_value((first, last)),
         (_, _) => None,
     }
 }
 
+fn checked_sum(mut values: impl Iterator<Item = u128>) -> Option<u128> {
+    values.try_fold(0_u128, |sum, v| sum.checked_add(v))
+}
+
+// Joins the first k and last k tokens into one number, or None if the line has
+// fewer than k tokens or the result doesn't fit in T
+fn digits_value<T: TryFrom<u128>>(tokens: &[&str], k: usize) -> Option<T> {
+    if k == 0 || tokens.len() < k {
+        return None;
+    }
+
+    tokens[..k]
+        .iter()
+        .chain(&tokens[tokens.len() - k..])
+        .try_fold(0_u128, |value, token| {
+            let digit = u128::try_from(parse_digit_word(token).ok()?).ok()?;
+            value.checked_mul(10)?.checked_add(digit)
+        })
+        .and_then(|value| T::try_from(value).ok())
+}
+
+// Joins the first and last numbers the same way format!("{first}{last}") would,
+// keeping any leading zeros on the last number
+fn numbers_value<T: TryFrom<u128>>((first, last): (&str, &str)) -> Option<T> {
+    let first = first.parse::<u128>().ok()?;
+    let last_value = last.parse::<u128>().ok()?;
+    let shift = 10_u128.checked_pow(last.len().try_into().ok()?)?;
+
+    first
+        .checked_mul(shift)?
+        .checked_add(last_value)
+        .and_then(|value| T::try_from(value).ok())
+}
+
 fn parse_digit_word(word_or_digit: &str) -> Result<i32, ParseIntError> {
     match word_or_digit.parse::<i32>() {
         Ok(num) => Ok(num),
@@ -46,30 +255,104 @@ fn parse_digit_word(word_or_digit: &str) -> Result<i32, ParseIntError> {
     }
 }
 
-fn problem_one<'a>(input: &'a str, re: &Regex) -> Option<(&'a str, &'a str)> {
-    let matches: Vec<regex::Match<'_>> = re.find_iter(input).collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    match (matches.first(), matches.last()) {
-        (Some(first_match), Some(second_match)) => {
-            Some((first_match.as_str(), second_match.as_str()))
-        }
-        (Some(first_match), None) => Some((first_match.as_str(), first_match.as_str())),
-        (None, Some(second_match)) => Some((second_match.as_str(), second_match.as_str())),
-        (_, _) => None,
+    #[test]
+    fn overlapping_words() {
+        let test = "xtwone3four";
+
+        assert_eq!(
+            calibration_value(test, CalibrationMode::Digits(1), &Patterns::new()),
+            Some(33)
+        );
     }
-}
 
+    #[test]
+    fn classic_digits() {
+        assert_eq!(digits_value::<u64>(&["1", "2", "3"], 1), Some(13));
+        assert_eq!(digits_value::<u64>(&["7"], 1), Some(77));
+        assert_eq!(digits_value::<u64>(&[], 1), None);
+    }
 
+    #[test]
+    fn wider_digits() {
+        let tokens = ["1", "2", "3", "4", "5"];
 
+        assert_eq!(digits_value::<u64>(&tokens, 2), Some(1245));
+        assert_eq!(digits_value::<u64>(&tokens, 5), Some(1234512345));
+        assert_eq!(digits_value::<u64>(&tokens, 6), None);
+        assert_eq!(digits_value::<u64>(&["two", "9"], 2), Some(2929));
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn digits_overflow() {
+        let tokens = ["9"; 10];
+
+        // 20 nines fits in a u128 but not a u64, 40 fits in neither
+        assert_eq!(digits_value::<u64>(&tokens, 10), None);
+        assert!(digits_value::<u128>(&tokens, 10).is_some());
+        assert_eq!(digits_value::<u128>(&["9"; 20], 20), None);
+    }
 
     #[test]
-    fn overlapping_words() {
-        let test = "xtwone3four";
+    fn whole_numbers() {
+        let chosen = chosen_tokens("ab12cd3ef007", CalibrationMode::Numbers, &Patterns::new());
+        let digits: Vec<&str> = chosen.iter().map(|c| c.digits.as_ref()).collect();
+
+        assert_eq!(digits, vec!["12", "007"]);
+        assert_eq!((chosen[1].start, chosen[1].end), (9, 12));
+        assert_eq!(numbers_value::<u64>(("12", "007")), Some(12007));
+        assert_eq!(numbers_value::<u64>(("5", "5")), Some(55));
+        assert_eq!(numbers_value::<u64>(("99999999999", "99999999999")), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn roman_without_words() {
+        parse_args(["--numbers", "--roman"].into_iter().map(str::to_string));
+    }
 
-        assert!
+    #[test]
+    fn mode_args() {
+        let args = |a: &[&str]| {
+            a.iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+
+        assert_eq!(parse_args(args(&[])).mode, CalibrationMode::Digits(1));
+        assert_eq!(
+            parse_args(args(&["--digits", "3"])).mode,
+            CalibrationMode::Digits(3)
+        );
+        assert_eq!(
+            parse_args(args(&["--numbers"])).mode,
+            CalibrationMode::Numbers
+        );
+        assert_eq!(
+            parse_args(args(&["--words", "--roman"])).mode,
+            CalibrationMode::Words { roman: true }
+        );
+        assert_eq!(
+            parse_args(args(&["--roman", "--words"])).mode,
+            CalibrationMode::Words { roman: true }
+        );
+        assert_eq!(
+            parse_args(args(&["--roman"])).mode,
+            CalibrationMode::Words { roman: true }
+        );
+        assert_eq!(
+            parse_args(args(&["--input", "big.txt", "--parallel"])),
+            Options {
+                mode: CalibrationMode::Digits(1),
+                roman: false,
+                input_path: Some("big.txt".to_string()),
+                parallel: true,
+                highlight: false,
+            }
+        );
     }
-}
\ No newline at end of file
+}
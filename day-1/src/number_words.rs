@@ -0,0 +1,211 @@
+// Recognises whole numbers in a line: runs of digits, number words from "zero"
+// to "ninety-nine" and, optionally, uppercase Roman numerals.
+//
+// Matches are allowed to overlap ("twone" holds both "two" and "one"), so every
+// position in the line is tried and the longest number starting there is kept.
+
+const UNITS: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+const TEENS: [&str; 10] = [
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+// indexed by the tens digit, so 0 and 1 are covered by UNITS and TEENS
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+// (one, five, ten) symbols for thousands, hundreds, tens and units
+const ROMAN_PLACES: [(char, char, char); 4] = [
+    ('M', '?', '?'),
+    ('C', 'D', 'M'),
+    ('X', 'L', 'C'),
+    ('I', 'V', 'X'),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberMatch {
+    pub start: usize,
+    pub end: usize,
+    // the number written out in digits, keeping any leading zeros a digit run had
+    pub decimal: String,
+}
+
+pub fn find_numbers(line: &str, roman: bool) -> Vec<NumberMatch> {
+    line.char_indices()
+        .filter_map(|(start, _)| {
+            let rest = &line[start..];
+            let (len, decimal) = digits_at(rest)
+                .or_else(|| word_number_at(rest).map(|(len, n)| (len, n.to_string())))
+                .or_else(|| {
+                    roman
+                        .then(|| roman_at(rest))
+                        .flatten()
+                        .map(|(len, n)| (len, n.to_string()))
+                })?;
+
+            Some(NumberMatch {
+                start,
+                end: start + len,
+                decimal,
+            })
+        })
+        .collect()
+}
+
+// The first number is the one starting furthest left and the last is the one
+// ending furthest right, so "twentyone" reads as 21 from both ends but
+// "eightwo" reads as 8 then 2. Ties go to the longer match.
+pub fn first_and_last(matches: &[NumberMatch]) -> Option<(&NumberMatch, &NumberMatch)> {
+    let first = matches
+        .iter()
+        .min_by_key(|m| (m.start, std::cmp::Reverse(m.end)))?;
+    let last = matches
+        .iter()
+        .max_by_key(|m| (m.end, std::cmp::Reverse(m.start)))?;
+
+    Some((first, last))
+}
+
+fn digits_at(input: &str) -> Option<(usize, String)> {
+    let len = input.bytes().take_while(|b| b.is_ascii_digit()).count();
+
+    (len > 0).then(|| (len, input[..len].to_string()))
+}
+
+fn word_number_at(input: &str) -> Option<(usize, u32)> {
+    let tens = (2..10).find(|&t| input.starts_with(TENS[t])).map(|t| {
+        let len = TENS[t].len();
+        let rest = &input[len..];
+
+        // "twenty-one", "twenty one" and "twentyone" are all 21
+        let compound = ["-", " ", ""].iter().find_map(|sep| {
+            let after_sep = rest.strip_prefix(sep)?;
+            let (unit_len, unit) = longest_prefix(after_sep, &UNITS[1..])?;
+            Some((len + sep.len() + unit_len, t as u32 * 10 + unit + 1))
+        });
+
+        compound.unwrap_or((len, t as u32 * 10))
+    });
+
+    let teens = longest_prefix(input, &TEENS).map(|(len, i)| (len, i + 10));
+    let units = longest_prefix(input, &UNITS);
+
+    [tens, teens, units]
+        .into_iter()
+        .flatten()
+        .max_by_key(|(len, _)| *len)
+}
+
+fn longest_prefix(input: &str, words: &[&str]) -> Option<(usize, u32)> {
+    words
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| input.starts_with(*w))
+        .max_by_key(|(_, w)| w.len())
+        .map(|(i, w)| (w.len(), i as u32))
+}
+
+// Reads the longest canonical Roman numeral (I to MMMCMXCIX) at the start of the input
+fn roman_at(input: &str) -> Option<(usize, u32)> {
+    let mut len = 0;
+    let mut value = 0;
+
+    for (place, (one, five, ten)) in ROMAN_PLACES.iter().enumerate() {
+        let rest = &input[len..];
+        let scale = 10_u32.pow(3 - place as u32);
+        let best = (1..10)
+            .filter(|&digit| place > 0 || digit <= 3)
+            .map(|digit| (digit, roman_digit(digit, *one, *five, *ten)))
+            .filter(|(_, written)| rest.starts_with(written.as_str()))
+            .max_by_key(|(_, written)| written.len());
+
+        if let Some((digit, written)) = best {
+            len += written.len();
+            value += digit * scale;
+        }
+    }
+
+    (len > 0).then_some((len, value))
+}
+
+fn roman_digit(digit: u32, one: char, five: char, ten: char) -> String {
+    match digit {
+        1..=3 => one.to_string().repeat(digit as usize),
+        4 => format!("{one}{five}"),
+        5..=8 => format!("{five}{}", one.to_string().repeat(digit as usize - 5)),
+        9 => format!("{one}{ten}"),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_last(line: &str, roman: bool) -> Option<(String, String)> {
+        let matches = find_numbers(line, roman);
+        first_and_last(&matches).map(|(f, l)| (f.decimal.clone(), l.decimal.clone()))
+    }
+
+    fn pair(first: &str, last: &str) -> Option<(String, String)> {
+        Some((first.to_string(), last.to_string()))
+    }
+
+    #[test]
+    fn simple_words() {
+        assert_eq!(first_last("zeroabc", false), pair("0", "0"));
+        assert_eq!(first_last("xtwone3four", false), pair("2", "4"));
+        assert_eq!(first_last("nothing here", false), None);
+    }
+
+    #[test]
+    fn teens_beat_units() {
+        assert_eq!(first_last("seventeen", false), pair("17", "17"));
+        assert_eq!(first_last("eighteenx", false), pair("18", "18"));
+        assert_eq!(first_last("xfourteen", false), pair("14", "14"));
+    }
+
+    #[test]
+    fn compound_words() {
+        assert_eq!(first_last("twenty-one", false), pair("21", "21"));
+        assert_eq!(first_last("forty two", false), pair("42", "42"));
+        assert_eq!(first_last("ninetynine", false), pair("99", "99"));
+        assert_eq!(first_last("sixty-", false), pair("60", "60"));
+        // "one" inside "seventyone" ends at the same place, so the longer 71 wins
+        assert_eq!(first_last("seventyone and 5", false), pair("71", "5"));
+    }
+
+    #[test]
+    fn overlapping_ends() {
+        // "eightwo" shares its t, so the last number is the two that ends later
+        assert_eq!(first_last("eightwo", false), pair("8", "2"));
+        assert_eq!(first_last("3twentyoneight", false), pair("3", "8"));
+        assert_eq!(first_last("oneighteen", false), pair("1", "18"));
+    }
+
+    #[test]
+    fn digit_runs() {
+        assert_eq!(first_last("ab012cd", false), pair("012", "012"));
+        assert_eq!(first_last("1abc23", false), pair("1", "23"));
+    }
+
+    #[test]
+    fn roman_numerals() {
+        assert_eq!(first_last("XIV then VI", true), pair("14", "6"));
+        assert_eq!(first_last("MCMXCIV", true), pair("1994", "1994"));
+        assert_eq!(first_last("XIV then VI", false), None);
+        // lowercase letters are never Roman, so words can't be misread
+        assert_eq!(first_last("mix five", true), pair("5", "5"));
+        // IIII isn't canonical, so it reads as III from either end
+        assert_eq!(first_last("IIII", true), pair("3", "3"));
+    }
+}
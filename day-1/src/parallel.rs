@@ -0,0 +1,108 @@
+// Memory maps large calibration files and sums them on rayon's thread pool.
+//
+// The input is cut into chunks just after a newline, so no line is ever split
+// between two chunks and the answer matches sum_calibration exactly.
+
+use std::fs::File;
+
+use memmap2::Mmap;
+use rayon::prelude::*;
+
+use crate::{sum_calibration, CalibrationMode, Patterns};
+
+// Small enough to keep every thread busy, big enough that the per-chunk
+// overhead disappears next to the regex work
+const MIN_CHUNK_BYTES: usize = 64 * 1024;
+
+pub fn map_input(path: &str) -> Mmap {
+    let file = File::open(path).unwrap_or_else(|e| panic!("couldn't open {path}: {e}"));
+
+    // Safety: the map is only read, and only for as long as main runs. Another
+    // process truncating the file underneath us is outside what we guard against.
+    unsafe { Mmap::map(&file) }.unwrap_or_else(|e| panic!("couldn't memory map {path}: {e}"))
+}
+
+pub fn par_sum_calibration(
+    input: &str,
+    mode: CalibrationMode,
+    patterns: &Patterns,
+) -> Option<u128> {
+    let chunk_bytes = (input.len() / (rayon::current_num_threads() * 4)).max(MIN_CHUNK_BYTES);
+
+    split_chunks(input, chunk_bytes)
+        .par_iter()
+        .map(|chunk| sum_calibration(chunk, mode, patterns))
+        .try_reduce(|| 0, |a, b| a.checked_add(b))
+}
+
+// Splits the input into pieces of at least chunk_bytes that each end just after
+// a newline (or at the end of the input)
+fn split_chunks(input: &str, chunk_bytes: usize) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = input;
+    let size = chunk_bytes.max(1);
+
+    while !rest.is_empty() {
+        // searching bytes rather than chars keeps multi-byte text from moving the
+        // cut, and a '\n' byte is always a char boundary
+        let cut = rest
+            .as_bytes()
+            .get(size..)
+            .and_then(|tail| tail.iter().position(|b| *b == b'\n'))
+            .map_or(rest.len(), |i| size + i + 1);
+        let (chunk, tail) = rest.split_at(cut);
+
+        chunks.push(chunk);
+        rest = tail;
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_end_on_newlines() {
+        let input = "ab\ncde\nf\n\nghij";
+        let chunks = split_chunks(input, 2);
+
+        assert_eq!(chunks, vec!["ab\n", "cde\n", "f\n\n", "ghij"]);
+        assert_eq!(chunks.concat(), input);
+        assert_eq!(split_chunks(input, 100), vec![input]);
+        assert!(split_chunks("", 4).is_empty());
+        assert_eq!(split_chunks("né\nçé\n", 2), vec!["né\n", "çé\n"]);
+    }
+
+    #[test]
+    fn matches_sequential_sum() {
+        let input = include_str!("../input.txt");
+        let patterns = Patterns::new();
+        let modes = [
+            CalibrationMode::Digits(1),
+            CalibrationMode::Digits(3),
+            CalibrationMode::Numbers,
+            CalibrationMode::Words { roman: true },
+        ];
+
+        for mode in modes {
+            let sequential = sum_calibration(input, mode, &patterns);
+            let parallel = split_chunks(input, 37)
+                .par_iter()
+                .map(|chunk| sum_calibration(chunk, mode, &patterns))
+                .try_reduce(|| 0, |a, b| a.checked_add(b));
+
+            assert_eq!(parallel, sequential);
+            assert_eq!(par_sum_calibration(input, mode, &patterns), sequential);
+        }
+    }
+
+    #[test]
+    fn maps_a_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt");
+        let mmap = map_input(path);
+
+        assert_eq!(&mmap[..], include_str!("../input.txt").as_bytes());
+    }
+}
@@ -0,0 +1,71 @@
+// The bag the elf draws cubes from, as colour -> number of cubes of that colour.
+//
+// Bags are written the same way a round of draws is, so "12 red, 13 green, 14 blue"
+// is the bag from the puzzle. Entries can be split over commas or lines, which
+// lets a bag file list one colour per line.
+
+use std::collections::BTreeMap;
+
+use crate::{flag_value, parse_draw};
+
+pub type Bag = BTreeMap<String, i32>;
+
+pub const DEFAULT_BAG: &str = "12 red, 13 green, 14 blue";
+
+pub fn parse_bag(bag_str: &str) -> Bag {
+    let mut bag = Bag::new();
+
+    bag_str
+        .split([',', '\n'])
+        .filter(|entry| !entry.trim().is_empty())
+        .map(parse_draw)
+        .for_each(|draw| {
+            if bag.insert(draw.colour.clone(), draw.count).is_some() {
+                panic!("the bag lists {} more than once!", draw.colour);
+            }
+        });
+
+    bag
+}
+
+// Reads the bag from `--bag "<cubes>"` or `--bag-file <path>`, falling back to the
+// puzzle's bag when neither is given
+pub fn bag_from_args(args: &[String]) -> Bag {
+    match (flag_value(args, "--bag"), flag_value(args, "--bag-file")) {
+        (Some(_), Some(_)) => panic!("only one of --bag and --bag-file can be given"),
+        (Some(bag_str), None) => parse_bag(bag_str),
+        (None, Some(path)) => parse_bag(
+            &std::fs::read_to_string(path).unwrap_or_else(|e| panic!("couldn't read {path}: {e}")),
+        ),
+        (None, None) => parse_bag(DEFAULT_BAG),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bag_parsing() {
+        let bag = parse_bag("5 purple, 3 orange\n1 red\n\n");
+
+        assert_eq!(bag.len(), 3);
+        assert_eq!(bag["purple"], 5);
+        assert_eq!(bag["orange"], 3);
+        assert_eq!(bag["red"], 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn repeated_colour() {
+        parse_bag("5 red, 6 red");
+    }
+
+    #[test]
+    fn bag_args() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(bag_from_args(&args(&[])), parse_bag(DEFAULT_BAG));
+        assert_eq!(bag_from_args(&args(&["--bag", "2 teal"]))["teal"], 2);
+    }
+}
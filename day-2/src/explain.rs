@@ -0,0 +1,199 @@
+// Explains part one's verdict for a game: which draws ruled an impossible game
+// out, or how close a possible game came to breaking each of the bag's limits.
+
+use std::fmt;
+
+use crate::bag::Bag;
+use crate::{draw_is_possible, Game, Id};
+
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    // counted from 1, the way the rounds read in the input
+    pub round: usize,
+    pub colour: String,
+    pub count: i32,
+    // None when the colour isn't in the bag at all
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Margin {
+    pub colour: String,
+    pub limit: i32,
+    // the round that drew the most of this colour and how many it drew, or None
+    // if the game never drew it
+    pub most_drawn: Option<(usize, i32)>,
+}
+
+impl Margin {
+    pub fn spare(&self) -> i32 {
+        self.limit - self.most_drawn.map_or(0, |(_, count)| count)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Impossible(Vec<Violation>),
+    Possible(Vec<Margin>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Explanation {
+    pub id: Id,
+    pub verdict: Verdict,
+}
+
+pub fn explain_game(game: &Game, bag: &Bag) -> Explanation {
+    let violations: Vec<Violation> = game
+        .rounds
+        .iter()
+        .enumerate()
+        .flat_map(|(i, round)| round.draws.iter().map(move |draw| (i + 1, draw)))
+        .filter(|(_, draw)| !draw_is_possible(draw, bag))
+        .map(|(round, draw)| Violation {
+            round,
+            colour: draw.colour.clone(),
+            count: draw.count,
+            limit: bag.get(&draw.colour).copied(),
+        })
+        .collect();
+
+    let verdict = if violations.is_empty() {
+        Verdict::Possible(margins(game, bag))
+    } else {
+        Verdict::Impossible(violations)
+    };
+
+    Explanation {
+        id: game.id,
+        verdict,
+    }
+}
+
+fn margins(game: &Game, bag: &Bag) -> Vec<Margin> {
+    bag.iter()
+        .map(|(colour, limit)| {
+            // the earliest round wins a tie, since max_by_key would pick the last
+            let most_drawn = game
+                .rounds
+                .iter()
+                .enumerate()
+                .flat_map(|(i, round)| round.draws.iter().map(move |draw| (i + 1, draw)))
+                .filter(|(_, draw)| draw.colour == *colour)
+                .map(|(round, draw)| (round, draw.count))
+                .fold(
+                    None,
+                    |best: Option<(usize, i32)>, (round, count)| match best {
+                        Some((_, best_count)) if best_count >= count => best,
+                        _ => Some((round, count)),
+                    },
+                );
+
+            Margin {
+                colour: colour.clone(),
+                limit: *limit,
+                most_drawn,
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.verdict {
+            Verdict::Impossible(violations) => {
+                writeln!(f, "Game {}: impossible", self.id)?;
+                for v in violations {
+                    match v.limit {
+                        Some(limit) => writeln!(
+                            f,
+                            "  round {}: {} {}, but the bag only holds {limit}",
+                            v.round, v.count, v.colour
+                        )?,
+                        None => writeln!(
+                            f,
+                            "  round {}: {} {}, but the bag has no {}",
+                            v.round, v.count, v.colour, v.colour
+                        )?,
+                    }
+                }
+            }
+            Verdict::Possible(margins) => {
+                writeln!(f, "Game {}: possible", self.id)?;
+                for m in margins {
+                    match m.most_drawn {
+                        Some((round, count)) => writeln!(
+                            f,
+                            "  {}: {} to spare ({count} in round {round} of {})",
+                            m.colour,
+                            m.spare(),
+                            m.limit
+                        )?,
+                        None => {
+                            writeln!(f, "  {}: {} to spare (never drawn)", m.colour, m.spare())?
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bag::{parse_bag, DEFAULT_BAG};
+    use crate::{parse_game, sample_games};
+
+    #[test]
+    fn impossible_game() {
+        let game = parse_game(
+            "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red, 2 purple",
+        );
+        let explanation = explain_game(&game, &parse_bag(DEFAULT_BAG));
+
+        assert_eq!(
+            explanation.verdict,
+            Verdict::Impossible(vec![
+                Violation {
+                    round: 1,
+                    colour: "red".to_string(),
+                    count: 20,
+                    limit: Some(12),
+                },
+                Violation {
+                    round: 3,
+                    colour: "purple".to_string(),
+                    count: 2,
+                    limit: None,
+                },
+            ])
+        );
+        assert_eq!(
+            explanation.to_string(),
+            "Game 3: impossible\n  round 1: 20 red, but the bag only holds 12\n  round 3: 2 purple, but the bag has no purple\n"
+        );
+    }
+
+    #[test]
+    fn possible_game() {
+        let game = &sample_games()[0];
+        let explanation = explain_game(game, &parse_bag("12 red, 13 green, 14 blue, 1 teal"));
+
+        let Verdict::Possible(margins) = &explanation.verdict else {
+            panic!("game 1 should be possible");
+        };
+        let spares: Vec<i32> = margins.iter().map(Margin::spare).collect();
+
+        // blue, green, red, teal
+        assert_eq!(spares, vec![8, 11, 8, 1]);
+        assert_eq!(margins[1].most_drawn, Some((2, 2)));
+        assert_eq!(margins[3].most_drawn, None);
+        assert_eq!(
+            explanation.to_string(),
+            "Game 1: possible\n  blue: 8 to spare (6 in round 2 of 14)\n  green: 11 to spare (2 in round 2 of 13)\n  red: 8 to spare (4 in round 1 of 12)\n  teal: 1 to spare (never drawn)\n"
+        );
+    }
+}
@@ -0,0 +1,334 @@
+// Writes games back out, either as puzzle text or as JSON/CSV for spreadsheets,
+// and reads the JSON/CSV back in.
+//
+// Puzzle text is written the normalised way: rounds split by "; " and cubes by
+// ", ", with the draws in the order they were written. Normalised input comes
+// back out exactly as it went in, and anything else only has its spacing fixed.
+//
+// Imported games are checked the way parsed text would be: every game has at
+// least one round, every round at least one draw, no count is negative and no
+// colour is empty or holds whitespace or any of `,;:`, so whatever imports can be
+// written back out in any format and read in again unchanged.
+//
+// CSV has one row per draw (game,round,colour,count,power), with the
+// game's power repeated on each of its rows. Powers are only there for reading;
+// importing works the games out from the rounds alone.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bag::Bag;
+use crate::query::Field;
+use crate::{Draw, Game, Id, Round};
+
+const CSV_HEADER: &str = "game,round,colour,count,power";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Format {
+        match name {
+            "text" | "txt" => Format::Text,
+            "json" => Format::Json,
+            "csv" => Format::Csv,
+            other => panic!("unknown format {other}, expected text, json or csv"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GameRecord {
+    id: Id,
+    rounds: Vec<Round>,
+    #[serde(default)]
+    power: i64,
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rounds: Vec<String> = self
+            .rounds
+            .iter()
+            .map(|round| {
+                round
+                    .draws
+                    .iter()
+                    .map(|draw| format!("{} {}", draw.count, draw.colour))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            })
+            .collect();
+
+        write!(f, "Game {}: {}", self.id, rounds.join("; "))
+    }
+}
+
+pub fn export_games(games: &[Game], bag: &Bag, format: Format) -> String {
+    match format {
+        Format::Text => games.iter().map(|game| format!("{game}\n")).collect(),
+        Format::Json => {
+            let records: Vec<GameRecord> = games
+                .iter()
+                .map(|game| GameRecord {
+                    id: game.id,
+                    rounds: game.rounds.clone(),
+                    power: Field::Power.value(game, bag),
+                })
+                .collect();
+            serde_json::to_string_pretty(&records).unwrap() + "\n"
+        }
+        Format::Csv => {
+            let mut csv = format!("{CSV_HEADER}\n");
+            for game in games {
+                let power = Field::Power.value(game, bag);
+                for (i, round) in game.rounds.iter().enumerate() {
+                    for draw in &round.draws {
+                        csv += &format!(
+                            "{},{},{},{},{power}\n",
+                            game.id,
+                            i + 1,
+                            draw.colour,
+                            draw.count
+                        );
+                    }
+                }
+            }
+            csv
+        }
+    }
+}
+
+pub fn import_games(input: &str, format: Format) -> Result<Vec<Game>, String> {
+    match format {
+        Format::Text => import_text(input),
+        Format::Json => {
+            let records: Vec<GameRecord> =
+                serde_json::from_str(input).map_err(|e| format!("bad JSON: {e}"))?;
+            records
+                .into_iter()
+                .map(|record| {
+                    let game = Game {
+                        id: record.id,
+                        rounds: record.rounds,
+                    };
+                    check_game(&game).map(|_| game)
+                })
+                .collect()
+        }
+        Format::Csv => import_csv(input),
+    }
+}
+
+fn check_game(game: &Game) -> Result<(), String> {
+    if game.rounds.is_empty() {
+        return Err(format!("game {} has no rounds", game.id));
+    }
+    for (i, round) in game.rounds.iter().enumerate() {
+        if round.draws.is_empty() {
+            return Err(format!("game {} round {} is empty", game.id, i + 1));
+        }
+        for draw in &round.draws {
+            check_draw(draw).map_err(|e| format!("game {} round {}: {e}", game.id, i + 1))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_draw(draw: &Draw) -> Result<(), String> {
+    if draw.count < 0 {
+        return Err(format!(
+            "{} {} is a negative count",
+            draw.count, draw.colour
+        ));
+    }
+    let bad_char = |c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':');
+    if draw.colour.is_empty() || draw.colour.contains(bad_char) {
+        return Err(format!("{:?} can't be a colour", draw.colour));
+    }
+    Ok(())
+}
+
+// Like crate::parse_game, but returns an error for a malformed line
+fn import_text(input: &str) -> Result<Vec<Game>, String> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_no, line)| {
+            let bad_line = |why: &str| format!("line {}: {why}: {line}", line_no + 1);
+            let (name, rounds) = line
+                .split_once(':')
+                .ok_or_else(|| bad_line("expected Game <id>: <rounds>"))?;
+            let id: Id = name
+                .split_whitespace()
+                .next_back()
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| bad_line("bad game id"))?;
+            let rounds = rounds
+                .split(';')
+                .map(|round| {
+                    let draws = round
+                        .split(',')
+                        .map(
+                            |draw| match draw.split_whitespace().collect::<Vec<_>>()[..] {
+                                [count, colour] => Ok(Draw {
+                                    colour: colour.to_string(),
+                                    count: count.parse().map_err(|_| bad_line("bad count"))?,
+                                }),
+                                _ => Err(bad_line("expected draws like 3 red")),
+                            },
+                        )
+                        .collect::<Result<_, _>>()?;
+                    Ok(Round { draws })
+                })
+                .collect::<Result<_, String>>()?;
+
+            let game = Game { id, rounds };
+            check_game(&game).map_err(|e| bad_line(&e))?;
+            Ok(game)
+        })
+        .collect()
+}
+
+fn import_csv(input: &str) -> Result<Vec<Game>, String> {
+    let mut lines = input.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == CSV_HEADER => {}
+        _ => return Err(format!("expected the CSV to start with {CSV_HEADER}")),
+    }
+
+    let mut games: Vec<Game> = vec![];
+    for (line_no, line) in lines.filter(|(_, l)| !l.trim().is_empty()) {
+        let bad_row = |why: &str| format!("line {}: {why}: {line}", line_no + 1);
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [id, round, colour, count, _power] = fields[..] else {
+            return Err(bad_row("expected 5 fields"));
+        };
+        let id: Id = id.parse().map_err(|_| bad_row("bad game id"))?;
+        let round: usize = round.parse().map_err(|_| bad_row("bad round number"))?;
+        let count: i32 = count.parse().map_err(|_| bad_row("bad count"))?;
+        let draw = Draw {
+            colour: colour.to_string(),
+            count,
+        };
+        check_draw(&draw).map_err(|e| bad_row(&e))?;
+
+        // rows are grouped by game and numbered by round, in the order export wrote them
+        if games.last().map(|g| g.id) != Some(id) {
+            games.push(Game { id, rounds: vec![] });
+        }
+        let game = games.last_mut().unwrap();
+        if round == game.rounds.len() + 1 {
+            game.rounds.push(Round::default());
+        } else if round != game.rounds.len() || round == 0 {
+            return Err(bad_row("rounds must be numbered 1, 2, 3... within a game"));
+        }
+        game.rounds.last_mut().unwrap().draws.push(draw);
+    }
+
+    Ok(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bag::{parse_bag, DEFAULT_BAG};
+    use crate::parse_game;
+
+    // already written the way Display writes games, so it comes back out as is
+    const WRITTEN: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 17: 8 green, 6 blue, 20 red; 5 purple
+";
+
+    fn games() -> Vec<Game> {
+        WRITTEN.lines().map(parse_game).collect()
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let normalised: String = games().iter().map(|g| format!("{g}\n")).collect();
+        let again: Vec<Game> = normalised.lines().map(parse_game).collect();
+
+        assert_eq!(again, games());
+        assert_eq!(export_games(&again, &Bag::new(), Format::Text), normalised);
+        assert_eq!(normalised, WRITTEN);
+        assert_eq!(
+            parse_game("Game 4: 2 red,1 blue,  3 red").to_string(),
+            "Game 4: 2 red, 1 blue, 3 red"
+        );
+        assert_eq!(
+            parse_game("Game 5: 1 blue, 2 green; 3 red").to_string(),
+            "Game 5: 1 blue, 2 green; 3 red"
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = export_games(&games(), &parse_bag(DEFAULT_BAG), Format::Json);
+
+        assert!(json.contains("\"power\": 48"));
+        assert_eq!(import_games(&json, Format::Json).unwrap(), games());
+        assert!(import_games("[{\"id\": 1}]", Format::Json).is_err());
+        assert_eq!(
+            import_games(
+                "[{\"id\": 1, \"rounds\": [[{\"colour\": \"red\", \"count\": 2}]]}]",
+                Format::Json
+            ),
+            Ok(vec![parse_game("Game 1: 2 red")])
+        );
+        assert!(import_games("[{\"id\": 1, \"rounds\": []}]", Format::Json).is_err());
+        assert!(import_games("[{\"id\": 1, \"rounds\": [[]]}]", Format::Json).is_err());
+        assert!(import_games(
+            "[{\"id\": 1, \"rounds\": [[{\"colour\": \"red\", \"count\": -2}]]}]",
+            Format::Json
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let csv = export_games(&games(), &parse_bag(DEFAULT_BAG), Format::Csv);
+
+        assert!(csv.starts_with("game,round,colour,count,power\n1,1,blue,3,48\n1,1,red,4,48\n"));
+        assert!(csv.ends_with("17,2,purple,5,4800\n"));
+        assert_eq!(import_games(&csv, Format::Csv).unwrap(), games());
+    }
+
+    #[test]
+    fn text_import() {
+        assert_eq!(import_games(WRITTEN, Format::Text), Ok(games()));
+        assert!(import_games("Game x: 1 red", Format::Text).is_err());
+        assert!(import_games("Game 1 1 red", Format::Text).is_err());
+        assert!(import_games("Game 1: ; 2 red", Format::Text).is_err());
+        assert!(import_games("Game 1: 2 light blue", Format::Text).is_err());
+        assert!(import_games("Game 1: -2 red", Format::Text).is_err());
+    }
+
+    #[test]
+    fn bad_colours() {
+        for colour in ["light blue", "a,b", "", "a;b"] {
+            let json = format!(
+                "[{{\"id\": 1, \"rounds\": [[{{\"colour\": {colour:?}, \"count\": 2}}]]}}]"
+            );
+            assert!(import_games(&json, Format::Json).is_err(), "{colour:?}");
+        }
+        assert!(import_games(&format!("{CSV_HEADER}\n1,1,,3,0"), Format::Csv).is_err());
+        assert!(import_games(&format!("{CSV_HEADER}\n1,1,dark red,3,0"), Format::Csv).is_err());
+    }
+
+    #[test]
+    fn bad_csv() {
+        assert!(import_games("id,colour\n1,red", Format::Csv).is_err());
+        assert!(import_games(&format!("{CSV_HEADER}\n1,1,red"), Format::Csv).is_err());
+        assert!(import_games(&format!("{CSV_HEADER}\n1,2,red,3,0"), Format::Csv).is_err());
+        assert!(import_games(&format!("{CSV_HEADER}\n1,1,red,x,0"), Format::Csv).is_err());
+        assert!(import_games(&format!("{CSV_HEADER}\n1,1,red,-3,0"), Format::Csv).is_err());
+    }
+}
@@ -0,0 +1,296 @@
+// Generates puzzle inputs from a bag, for testing part one against answers that
+// are known up front.
+//
+// Each round draws a random number of cubes from the bag without replacement
+// (and puts them back before the next round), so every generated game is
+// possible. A chosen share of the games is then broken by pushing one colour in
+// one round over the bag's limit, which means the part one answer is just the
+// sum of the IDs that were left alone. The fewest cubes each game could have
+// been played with is kept track of as its rounds are drawn and broken, so part
+// two has a known answer too.
+//
+// The generator uses its own SplitMix64 so that a seed always gives the same
+// games, whatever version of any random number crate is around.
+
+use std::collections::BTreeMap;
+
+use crate::bag::Bag;
+use crate::{Draw, Game, Id, Round};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorConfig {
+    pub seed: u64,
+    pub games: usize,
+    pub max_rounds: usize,
+    // the share of games to make impossible, from 0.0 to 1.0
+    pub impossible_fraction: f64,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            seed: 2023,
+            games: 100,
+            max_rounds: 6,
+            impossible_fraction: 0.25,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Generated {
+    pub games: Vec<Game>,
+    pub expected_part_one: i64,
+    // the fewest cubes of each colour in the bag each game needs, game by game
+    pub minimum_bags: Vec<Bag>,
+}
+
+impl Generated {
+    pub fn expected_part_two(&self) -> i64 {
+        self.minimum_bags
+            .iter()
+            .map(|minimum| {
+                minimum
+                    .values()
+                    .map(|count| i64::from(*count))
+                    .product::<i64>()
+            })
+            .sum()
+    }
+}
+
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // A number in 0..n, with the modulo bias small enough not to matter here
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+pub fn generate_games(bag: &Bag, config: &GeneratorConfig) -> Generated {
+    let cubes: Vec<&String> = bag
+        .iter()
+        .flat_map(|(colour, count)| std::iter::repeat_n(colour, (*count).max(0) as usize))
+        .collect();
+    assert!(
+        !cubes.is_empty(),
+        "the bag needs at least one cube to draw from"
+    );
+
+    let mut rng = SplitMix64::new(config.seed);
+    let mut minimum_bags: Vec<Bag> = vec![];
+    let mut games: Vec<Game> = (1..=config.games)
+        .map(|id| {
+            let mut minimum: Bag = bag.keys().map(|colour| (colour.clone(), 0)).collect();
+            let rounds = (0..1 + rng.below(config.max_rounds.max(1)))
+                .map(|_| draw_round(&cubes, &mut minimum, &mut rng))
+                .collect();
+            minimum_bags.push(minimum);
+            Game {
+                id: Id::try_from(id).expect("too many games for their IDs to fit in an i32"),
+                rounds,
+            }
+        })
+        .collect();
+
+    let impossible_count =
+        ((config.impossible_fraction.clamp(0.0, 1.0) * config.games as f64).round()) as usize;
+    let mut order: Vec<usize> = (0..games.len()).collect();
+    for i in 0..impossible_count {
+        let j = i + rng.below(order.len() - i);
+        order.swap(i, j);
+        break_game(
+            &mut games[order[i]],
+            bag,
+            &mut minimum_bags[order[i]],
+            &mut rng,
+        );
+    }
+
+    let mut broken = vec![false; games.len()];
+    order[..impossible_count]
+        .iter()
+        .for_each(|i| broken[*i] = true);
+    let expected_part_one = games
+        .iter()
+        .zip(&broken)
+        .filter(|(_, broken)| !**broken)
+        .map(|(game, _)| i64::from(game.id))
+        .sum();
+
+    Generated {
+        games,
+        expected_part_one,
+        minimum_bags,
+    }
+}
+
+// Draws one round, raising the game's minimum bag to cover it
+fn draw_round(cubes: &[&String], minimum: &mut Bag, rng: &mut SplitMix64) -> Round {
+    let mut cubes = cubes.to_vec();
+    let drawn = 1 + rng.below(cubes.len());
+    let mut counts: BTreeMap<String, i32> = BTreeMap::new();
+
+    // a partial Fisher-Yates shuffle picks `drawn` cubes without replacement
+    for i in 0..drawn {
+        let j = i + rng.below(cubes.len() - i);
+        cubes.swap(i, j);
+        *counts.entry(cubes[i].clone()).or_insert(0) += 1;
+    }
+
+    counts.iter().for_each(|(colour, count)| {
+        let most = minimum.get_mut(colour).unwrap();
+        *most = (*most).max(*count);
+    });
+
+    Round {
+        draws: counts
+            .into_iter()
+            .map(|(colour, count)| Draw { colour, count })
+            .collect(),
+    }
+}
+
+// Pushes one colour in one round over the bag's limit. Nothing drawn from the bag
+// can show that many, so the new count is also the game's minimum for the colour.
+fn break_game(game: &mut Game, bag: &Bag, minimum: &mut Bag, rng: &mut SplitMix64) {
+    let round = rng.below(game.rounds.len());
+    let (colour, limit) = bag.iter().nth(rng.below(bag.len())).unwrap();
+
+    let count = limit + 1 + rng.below(3) as i32;
+    let draws = &mut game.rounds[round].draws;
+    draws.retain(|draw| draw.colour != *colour);
+    draws.push(Draw {
+        colour: colour.clone(),
+        count,
+    });
+    minimum.insert(colour.clone(), count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bag::{parse_bag, DEFAULT_BAG};
+    use crate::query::Field;
+    use crate::query::Query;
+    use crate::{draw_is_possible, get_game_power, parse_game};
+
+    fn part_one(games: &[Game], bag: &Bag) -> i64 {
+        let possible = Query::Possible;
+        games
+            .iter()
+            .filter(|g| possible.matches(g, bag))
+            .map(|g| i64::from(g.id))
+            .sum()
+    }
+
+    #[test]
+    fn same_seed_same_games() {
+        let bag = parse_bag(DEFAULT_BAG);
+        let config = GeneratorConfig::default();
+
+        assert_eq!(generate_games(&bag, &config), generate_games(&bag, &config));
+        assert_ne!(
+            generate_games(&bag, &config).games,
+            generate_games(&bag, &GeneratorConfig { seed: 7, ..config }).games
+        );
+    }
+
+    #[test]
+    fn possible_games_stay_in_the_bag() {
+        let bag = parse_bag("5 purple, 3 orange, 1 teal");
+        let config = GeneratorConfig {
+            impossible_fraction: 0.0,
+            games: 200,
+            ..GeneratorConfig::default()
+        };
+        let generated = generate_games(&bag, &config);
+
+        assert!(generated
+            .games
+            .iter()
+            .all(|g| g.draws().all(|d| draw_is_possible(d, &bag))));
+        assert!(generated
+            .games
+            .iter()
+            .all(|g| g.rounds.iter().all(|r| r.total() <= 9)));
+        assert_eq!(generated.expected_part_one, (1..=200).sum::<i64>());
+    }
+
+    #[test]
+    fn expected_answer_matches_part_one() {
+        let bag = parse_bag(DEFAULT_BAG);
+
+        for seed in 0..20 {
+            let config = GeneratorConfig {
+                seed,
+                games: 50,
+                impossible_fraction: 0.3,
+                ..GeneratorConfig::default()
+            };
+            let generated = generate_games(&bag, &config);
+            let impossible = generated
+                .games
+                .iter()
+                .filter(|g| !g.draws().all(|d| draw_is_possible(d, &bag)))
+                .count();
+
+            assert_eq!(impossible, 15);
+            assert_eq!(
+                part_one(&generated.games, &bag),
+                generated.expected_part_one
+            );
+        }
+    }
+
+    #[test]
+    fn minimum_bags_match_the_game_power() {
+        let bag = parse_bag("5 purple, 3 orange, 1 teal");
+
+        for seed in 0..20 {
+            let config = GeneratorConfig {
+                seed,
+                games: 50,
+                impossible_fraction: 0.5,
+                ..GeneratorConfig::default()
+            };
+            let generated = generate_games(&bag, &config);
+            let part_two: i64 = generated
+                .games
+                .iter()
+                .map(|g| Field::Power.value(g, &bag))
+                .sum();
+
+            for (game, minimum) in generated.games.iter().zip(&generated.minimum_bags) {
+                assert_eq!(&get_game_power(game, &bag), minimum);
+            }
+            assert_eq!(part_two, generated.expected_part_two());
+        }
+    }
+
+    #[test]
+    fn survives_the_text_format() {
+        let bag = parse_bag(DEFAULT_BAG);
+        let generated = generate_games(&bag, &GeneratorConfig::default());
+        let reparsed: Vec<Game> = generated
+            .games
+            .iter()
+            .map(|g| parse_game(&g.to_string()))
+            .collect();
+
+        assert_eq!(reparsed, generated.games);
+    }
+}
@@ -0,0 +1,267 @@
+// Works backwards from the games to the bag they were played with.
+//
+// The minimum bag is the smallest one every game is possible with. The most
+// likely bag treats each round as cubes drawn without replacement from the bag
+// (and put back before the next round), so a round showing k_c cubes of each
+// colour c from a bag of N_c cubes, N in total, has probability
+//
+//   product over c of C(N_c, k_c) / C(N, sum of k_c)
+//
+// and the estimate is a bag maximising the product of that over every round. It
+// is found by hill climbing from the fewest cubes each round needs, so it's the
+// best bag within one cube of it rather than a guaranteed best over every bag.
+// Bigger bags can keep getting more likely when the rounds are very even, so the
+// search is capped at a number of cubes per colour.
+//
+// A round that lists a colour twice had both draws out of the bag together, so
+// the likelihood, unlike the minimum bag, adds them up.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::bag::Bag;
+use crate::{get_game_power, Game, Round};
+
+pub const DEFAULT_MAX_PER_COLOUR: i32 = 100;
+
+// A drop of 1.92 in log likelihood is the 95% cut off for one parameter (half
+// the 3.84 of a chi-squared distribution with one degree of freedom)
+const INTERVAL_DROP: f64 = 1.92;
+
+#[derive(Debug, PartialEq)]
+pub struct Estimate {
+    pub bag: Bag,
+    // colour -> the range of counts still within the 95% interval when the
+    // other colours are held at their estimates
+    pub intervals: BTreeMap<String, (i32, i32)>,
+    pub max_per_colour: i32,
+}
+
+pub fn minimum_bag(games: &[Game]) -> Bag {
+    games.iter().fold(Bag::new(), |mut bag, game| {
+        for (colour, count) in get_game_power(game, &Bag::new()) {
+            let max = bag.entry(colour).or_insert(0);
+            *max = count.max(*max);
+        }
+        bag
+    })
+}
+
+// The fewest cubes of each colour that could show every round, with a colour
+// listed twice in a round counted once for all of them
+fn fewest_per_round(games: &[Game]) -> Bag {
+    let mut fewest = Bag::new();
+    games.iter().flat_map(|g| &g.rounds).for_each(|round| {
+        for (colour, count) in round.counts() {
+            let max = fewest.entry(colour).or_insert(0);
+            *max = count.max(*max);
+        }
+    });
+    fewest
+}
+
+pub fn most_likely_bag(games: &[Game], max_per_colour: i32) -> Estimate {
+    let rounds: Vec<&Round> = games.iter().flat_map(|g| &g.rounds).collect();
+    let minimum = fewest_per_round(games);
+    let max_per_colour = max_per_colour.max(minimum.values().copied().max().unwrap_or(0));
+    let ln_factorials = ln_factorials(max_per_colour as usize * minimum.len());
+    let score = |bag: &Bag| log_likelihood(&rounds, bag, &ln_factorials);
+
+    let colours: Vec<&String> = minimum.keys().collect();
+    let mut bag = minimum.clone();
+    let mut best = score(&bag);
+
+    // hill climb one cube at a time: add or remove a cube of one colour, or swap
+    // a cube of one colour for another, keeping whichever move helps most
+    loop {
+        let mut moves: Vec<Vec<(&String, i32)>> = vec![];
+        for c in &colours {
+            moves.push(vec![(c, 1)]);
+            moves.push(vec![(c, -1)]);
+            for other in &colours {
+                if c != other {
+                    moves.push(vec![(c, 1), (other, -1)]);
+                }
+            }
+        }
+
+        let best_move = moves
+            .iter()
+            .filter_map(|m| {
+                let mut candidate = bag.clone();
+                for (colour, step) in m {
+                    *candidate.get_mut(*colour).unwrap() += step;
+                }
+                let valid = candidate
+                    .iter()
+                    .all(|(colour, n)| *n >= minimum[colour] && *n <= max_per_colour);
+                valid.then(|| {
+                    let s = score(&candidate);
+                    (candidate, s)
+                })
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        match best_move {
+            Some((candidate, s)) if s > best + 1e-12 => {
+                bag = candidate;
+                best = s;
+            }
+            _ => break,
+        }
+    }
+
+    let intervals = colours
+        .iter()
+        .map(|colour| {
+            let within = |n: i32| {
+                let mut candidate = bag.clone();
+                candidate.insert(colour.to_string(), n);
+                score(&candidate) >= best - INTERVAL_DROP
+            };
+            let low = (minimum[*colour]..=bag[*colour])
+                .find(|n| within(*n))
+                .unwrap_or(bag[*colour]);
+            let high = (bag[*colour]..=max_per_colour)
+                .take_while(|n| within(*n))
+                .last()
+                .unwrap_or(bag[*colour]);
+
+            (colour.to_string(), (low, high))
+        })
+        .collect();
+
+    Estimate {
+        bag,
+        intervals,
+        max_per_colour,
+    }
+}
+
+pub fn log_likelihood(rounds: &[&Round], bag: &Bag, ln_factorials: &[f64]) -> f64 {
+    let total: i32 = bag.values().sum();
+    let ln_choose = |n: i32, k: i32| {
+        if k < 0 || k > n {
+            f64::NEG_INFINITY
+        } else {
+            ln_factorials[n as usize] - ln_factorials[k as usize] - ln_factorials[(n - k) as usize]
+        }
+    };
+
+    rounds
+        .iter()
+        .map(|round| {
+            let drawn = round.total();
+            let ways: f64 = round
+                .counts()
+                .iter()
+                .map(|(colour, k)| ln_choose(bag.get(colour).copied().unwrap_or(0), *k))
+                .sum();
+            ways - ln_choose(total, drawn)
+        })
+        .sum()
+}
+
+// ln(n!) for every n up to and including max
+pub fn ln_factorials(max: usize) -> Vec<f64> {
+    let mut table = vec![0.0; max + 1];
+    for n in 1..=max {
+        table[n] = table[n - 1] + (n as f64).ln();
+    }
+    table
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Most likely bag, best within one cube (cubes put back between rounds, at most {} of a colour):",
+            self.max_per_colour
+        )?;
+        for (colour, count) in &self.bag {
+            let (low, high) = self.intervals[colour];
+            let capped = if high == self.max_per_colour { "+" } else { "" };
+            writeln!(
+                f,
+                "  {colour}: {count} (95% interval {low} to {high}{capped})"
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bag::parse_bag;
+    use crate::{parse_game, parse_round, sample_games};
+
+    #[test]
+    fn smallest_bag() {
+        assert_eq!(
+            minimum_bag(&sample_games()),
+            parse_bag("20 red, 13 green, 15 blue")
+        );
+    }
+
+    #[test]
+    fn round_likelihood() {
+        let round = parse_round("1 red, 1 blue");
+        let table = ln_factorials(10);
+
+        // 2 red and 3 blue: C(2,1) * C(3,1) / C(5,2) = 6 / 10
+        let ln_l = log_likelihood(&[&round], &parse_bag("2 red, 3 blue"), &table);
+        assert!((ln_l - 0.6_f64.ln()).abs() < 1e-9);
+
+        // a bag without enough blue can't produce the round at all
+        let ln_l = log_likelihood(&[&round], &parse_bag("2 red, 0 blue"), &table);
+        assert_eq!(ln_l, f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn whole_bag_every_round() {
+        // drawing everything each time is only certain when there's nothing left over
+        let games: Vec<Game> = (1..=10)
+            .map(|id| parse_game(&format!("Game {id}: 3 red, 2 blue")))
+            .collect();
+        let estimate = most_likely_bag(&games, 50);
+
+        assert_eq!(estimate.bag, parse_bag("3 red, 2 blue"));
+        assert_eq!(estimate.intervals["red"], (3, 3));
+        assert_eq!(estimate.intervals["blue"], (2, 2));
+    }
+
+    #[test]
+    fn repeated_colour_in_a_round() {
+        let games = vec![parse_game("Game 1: 7 red, 7 red; 3 red")];
+        let estimate = most_likely_bag(&games, 50);
+
+        assert_eq!(minimum_bag(&games), parse_bag("7 red"));
+        assert_eq!(fewest_per_round(&games), parse_bag("14 red"));
+        // a single colour is drawn with certainty from any bag big enough
+        assert_eq!(estimate.bag, parse_bag("14 red"));
+        assert_eq!(estimate.intervals["red"], (14, 50));
+    }
+
+    #[test]
+    fn estimate_is_a_local_maximum() {
+        let games = sample_games();
+        let estimate = most_likely_bag(&games, 60);
+        let rounds: Vec<&Round> = games.iter().flat_map(|g| &g.rounds).collect();
+        let table = ln_factorials(60 * 3);
+        let best = log_likelihood(&rounds, &estimate.bag, &table);
+
+        for colour in estimate.bag.keys() {
+            for step in [-1, 1] {
+                let mut nearby = estimate.bag.clone();
+                *nearby.get_mut(colour).unwrap() += step;
+                if nearby[colour] >= fewest_per_round(&games)[colour] && nearby[colour] <= 60 {
+                    assert!(log_likelihood(&rounds, &nearby, &table) <= best);
+                }
+            }
+            let (low, high) = estimate.intervals[colour];
+            assert!(low <= estimate.bag[colour] && estimate.bag[colour] <= high);
+        }
+    }
+}
@@ -1,54 +1,265 @@
-#[derive(PartialEq, Debug)]
-enum Draw {
-    Red(i32),
-    Green(i32),
-    Blue(i32),
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use bag::{bag_from_args, Bag};
+use explain::explain_game;
+use export::{export_games, import_games, Format};
+use generate::{generate_games, GeneratorConfig};
+use infer::{minimum_bag, most_likely_bag, DEFAULT_MAX_PER_COLOUR};
+use query::{known_colours, parse_query, Field, Query};
+use rules::{rules_from_args, rules_report};
+use stats::stats_report;
+
+mod bag;
+mod explain;
+mod export;
+mod generate;
+mod infer;
+mod query;
+mod rules;
+mod stats;
+
+const COMMANDS: &str = "explain, infer, query, export, import, generate, stats, rules";
+// flags that take the argument after them as their value
+const VALUE_FLAGS: [&str; 11] = [
+    "--bag",
+    "--bag-file",
+    "--input",
+    "--max-cubes",
+    "--sum",
+    "--format",
+    "--seed",
+    "--games",
+    "--max-rounds",
+    "--impossible",
+    "--round-total",
+];
+// flags that are just there or not
+const SWITCHES: [&str; 3] = ["--count", "--no-replacement", "--every-colour"];
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+struct Draw {
+    colour: String,
+    count: i32,
 }
 type Id = i32;
-type Game = (Id, Vec<Draw>);
 
-const MAX_RED: i32 = 12;
-const MAX_GREEN: i32 = 13;
-const MAX_BLUE: i32 = 14;
+// The cubes shown together in one round, kept as written, so a colour listed
+// twice ("3 red, 2 red") stays as two draws
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct Round {
+    draws: Vec<Draw>,
+}
+
+impl Round {
+    // colour -> cubes of that colour out of the bag during the round, adding up
+    // any colour listed more than once
+    fn counts(&self) -> BTreeMap<String, i32> {
+        let mut counts = BTreeMap::new();
+        self.draws.iter().for_each(|draw| {
+            *counts.entry(draw.colour.clone()).or_insert(0) += draw.count;
+        });
+        counts
+    }
+
+    fn total(&self) -> i32 {
+        self.draws.iter().map(|draw| draw.count).sum()
+    }
+
+    fn repeats_colour(&self) -> bool {
+        self.counts().len() < self.draws.len()
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+struct Game {
+    id: Id,
+    rounds: Vec<Round>,
+}
+
+impl Game {
+    // Every colour and count shown in the game as written, round by round
+    fn draws(&self) -> impl Iterator<Item = &Draw> + '_ {
+        self.rounds.iter().flat_map(|round| &round.draws)
+    }
+}
 
 fn main() {
-    let input = include_str!("../input.txt");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let bag = bag_from_args(&args);
+    let positional = positional_args(&args);
+    let command = positional.first().copied();
+
+    // `--input <path>` swaps the puzzle input for another file, like a generated one
+    let input = match flag_value(&args, "--input") {
+        Some(path) => {
+            std::fs::read_to_string(path).unwrap_or_else(|e| panic!("couldn't read {path}: {e}"))
+        }
+        None => include_str!("../input.txt").to_string(),
+    };
+    let parsed_games: Vec<Game> = input.lines().map(parse_game).collect();
 
-    let parsed_games = input.lines().map(parse_game);
+    match command.map(String::as_str) {
+        None => solve(&parsed_games, &bag),
+        Some("explain") => parsed_games
+            .iter()
+            .for_each(|game| print!("{}", explain_game(game, &bag))),
+        Some("infer") => {
+            let max_per_colour =
+                parsed_flag(&args, "--max-cubes").unwrap_or(DEFAULT_MAX_PER_COLOUR);
+            assert!(max_per_colour >= 1, "--max-cubes needs to be at least 1");
+            let minimum: Vec<String> = minimum_bag(&parsed_games)
+                .iter()
+                .map(|(colour, count)| format!("{count} {colour}"))
+                .collect();
 
+            println!("Minimum bag for every game: {}", minimum.join(", "));
+            print!("{}", most_likely_bag(&parsed_games, max_per_colour));
+        }
+        Some("query") => {
+            let query_str = positional
+                .get(1)
+                .expect("query needs an expression like \"red > 10\"");
+            let colours = known_colours(&parsed_games, &bag);
+            let query =
+                parse_query(query_str, &colours).unwrap_or_else(|e| panic!("bad query: {e}"));
+            let matching = parsed_games.iter().filter(|game| query.matches(game, &bag));
+
+            if args.iter().any(|a| a == "--count") {
+                println!("{}", matching.count());
+            } else if let Some(field) = flag_value(&args, "--sum") {
+                let field =
+                    Field::parse(field, &colours).unwrap_or_else(|e| panic!("bad --sum: {e}"));
+                println!(
+                    "{}",
+                    matching.map(|game| field.value(game, &bag)).sum::<i64>()
+                );
+            } else {
+                matching.for_each(|game| println!("{}", game.id));
+            }
+        }
+        Some("export") => {
+            let format = flag_value(&args, "--format").map_or(Format::Text, |f| Format::parse(f));
+            print!("{}", export_games(&parsed_games, &bag, format));
+        }
+        Some("import") => {
+            let path = positional
+                .get(1)
+                .expect("import needs a file exported as json or csv");
+            let format = match flag_value(&args, "--format") {
+                Some(format) => Format::parse(format),
+                None => Format::parse(path.rsplit('.').next().unwrap_or_default()),
+            };
+            let contents = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("couldn't read {path}: {e}"));
+            let games = import_games(&contents, format).unwrap_or_else(|e| panic!("{path}: {e}"));
+
+            print!("{}", export_games(&games, &bag, Format::Text));
+        }
+        Some("generate") => {
+            let defaults = GeneratorConfig::default();
+            let config = GeneratorConfig {
+                seed: parsed_flag(&args, "--seed").unwrap_or(defaults.seed),
+                games: parsed_flag(&args, "--games").unwrap_or(defaults.games),
+                max_rounds: parsed_flag(&args, "--max-rounds").unwrap_or(defaults.max_rounds),
+                impossible_fraction: parsed_flag(&args, "--impossible")
+                    .unwrap_or(defaults.impossible_fraction),
+            };
+            let generated = generate_games(&bag, &config);
+
+            // the games go to stdout so they can be redirected into a file
+            print!("{}", export_games(&generated.games, &bag, Format::Text));
+            eprintln!("Expected part one answer: {}", generated.expected_part_one);
+            eprintln!(
+                "Expected part two answer: {}",
+                generated.expected_part_two()
+            );
+        }
+        Some("stats") => print!("{}", stats_report(&parsed_games, &bag)),
+        Some("rules") => print!(
+            "{}",
+            rules_report(&rules_from_args(&args), &parsed_games, &bag)
+        ),
+        Some(other) => panic!("unknown command {other}, expected one of {COMMANDS} or no command"),
+    }
+}
+
+// The arguments that aren't flags or their values, i.e. the command and what it
+// works on, wherever they come among the flags
+fn positional_args(args: &[String]) -> Vec<&String> {
+    let mut positional = vec![];
+    let mut rest = args.iter();
+
+    while let Some(arg) = rest.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            rest.next();
+        } else if arg.starts_with("--") {
+            if !SWITCHES.contains(&arg.as_str()) {
+                panic!("unknown flag {arg}");
+            }
+        } else {
+            positional.push(arg);
+        }
+    }
+
+    positional
+}
+
+// The argument following a flag like `--bag`, if the flag was given
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter().position(|a| a == flag).map(|i| {
+        args.get(i + 1)
+            .unwrap_or_else(|| panic!("{flag} needs a value"))
+    })
+}
+
+// The argument following a flag, parsed as whatever type the flag takes
+fn parsed_flag<T: std::str::FromStr>(args: &[String], flag: &str) -> Option<T> {
+    flag_value(args, flag).map(|value| {
+        value.parse().unwrap_or_else(|_| {
+            panic!(
+                "{flag}: {value} isn't a valid {}",
+                std::any::type_name::<T>()
+            )
+        })
+    })
+}
+
+fn solve(parsed_games: &[Game], bag: &Bag) {
+    let possible = Query::Possible;
     let possible_games = parsed_games
-        .clone()
-        .filter(|(_, draws)| draws.into_iter().all(draw_is_possible));
-    let summed_ids = possible_games.map(|(index, _)| index).sum::<i32>();
+        .iter()
+        .filter(|game| possible.matches(game, bag));
+    let summed_ids: i64 = possible_games.map(|game| i64::from(game.id)).sum();
     println!("Problem 1: The sum of the ID's of the possible games is {summed_ids}");
 
-    let summed_powers: i32 = parsed_games
-        .map(get_game_power)
-        .map(|(r, g, b)| r * g * b)
+    let summed_powers: i64 = parsed_games
+        .iter()
+        .map(|game| Field::Power.value(game, bag))
         .sum();
 
     println!("Problem 2: The sum of the minimum powers for all games is {summed_powers}");
 }
 
-fn get_game_power(game: Game) -> (i32, i32, i32) {
-    let (_, draws) = game;
-    let mut maxes = (0, 0, 0);
+// The fewest cubes of each colour the game could have been played with. Every
+// colour in the bag is included, so a colour the game never showed makes the
+// power 0, just as it did with the fixed red/green/blue bag.
+fn get_game_power(game: &Game, bag: &Bag) -> Bag {
+    let mut maxes: Bag = bag.keys().map(|colour| (colour.clone(), 0)).collect();
 
-    draws.iter().for_each(|d| match d {
-        Draw::Red(count) => maxes.0 = *count.max(&maxes.0),
-        Draw::Green(count) => maxes.1 = *count.max(&maxes.1),
-        Draw::Blue(count) => maxes.2 = *count.max(&maxes.2),
+    game.draws().for_each(|d| {
+        let max = maxes.entry(d.colour.clone()).or_insert(0);
+        *max = d.count.max(*max);
     });
 
     maxes
 }
 
-fn draw_is_possible(draw: &Draw) -> bool {
-    match draw {
-        Draw::Red(count) => count <= &MAX_RED,
-        Draw::Green(count) => count <= &MAX_GREEN,
-        Draw::Blue(count) => count <= &MAX_BLUE,
-    }
+fn draw_is_possible(draw: &Draw, bag: &Bag) -> bool {
+    // colours that aren't in the bag at all can't be drawn
+    bag.get(&draw.colour).is_some_and(|max| draw.count <= *max)
 }
 
 fn parse_game(game_str: &str) -> Game {
@@ -61,66 +272,154 @@ fn parse_game(game_str: &str) -> Game {
         .unwrap()
         .parse::<i32>()
         .unwrap();
-    let draws = game_split.last().unwrap().split(';');
+    let rounds = game_split.next_back().unwrap().split(';');
 
-    (
+    Game {
         id,
-        draws
-            .flat_map(|d| d.split(',').map(parse_draw).collect::<Vec<Draw>>())
-            .collect(),
-    )
+        rounds: rounds.map(parse_round).collect(),
+    }
+}
+
+fn parse_round(round_str: &str) -> Round {
+    Round {
+        draws: round_str.split(',').map(parse_draw).collect(),
+    }
 }
 
 fn parse_draw(cube: &str) -> Draw {
     let mut results = cube.split_whitespace();
     let count = results.next().unwrap().parse::<i32>().unwrap();
-    let label = results.last().unwrap().trim();
+    let colour = results
+        .next_back()
+        .expect("there is a cube's text that has no color!")
+        .trim()
+        .to_string();
 
-    match label {
-        "red" => Draw::Red(count),
-        "green" => Draw::Green(count),
-        "blue" => Draw::Blue(count),
-        _ => panic!("there is a cube's text that has a non-standard color!"),
-    }
+    Draw { colour, count }
+}
+
+// The five example games from the puzzle, shared by the tests of every module
+#[cfg(test)]
+fn sample_games() -> Vec<Game> {
+    [
+        "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green",
+        "Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue",
+        "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+        "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red",
+        "Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green",
+    ]
+    .into_iter()
+    .map(parse_game)
+    .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bag::{parse_bag, DEFAULT_BAG};
+
+    fn draw(count: i32, colour: &str) -> Draw {
+        Draw {
+            colour: colour.to_string(),
+            count,
+        }
+    }
 
     #[test]
     fn cube_parsing() {
-        assert_eq!(parse_draw("14 red"), Draw::Red(14));
-        assert_eq!(parse_draw("17 green"), Draw::Green(17));
-        assert_eq!(parse_draw("1 blue"), Draw::Blue(1));
+        assert_eq!(parse_draw("14 red"), draw(14, "red"));
+        assert_eq!(parse_draw("17 green"), draw(17, "green"));
+        assert_eq!(parse_draw("1 blue"), draw(1, "blue"));
+        assert_eq!(parse_draw(" 14 vermillion"), draw(14, "vermillion"));
     }
 
     #[test]
     #[should_panic]
     fn cube_failing() {
-        parse_draw("14 vermillion");
+        parse_draw("14");
     }
 
     #[test]
     fn get_power() {
-        use crate::Draw::{Blue, Green, Red};
-        // Game 100: 1 blue, 13 green, 14 red; 11 green, 11 blue, 7 red; 2 red, 1 blue, 2 green; 10 blue, 15 red
-        let game: Game = (
-            100,
+        let game = parse_game("Game 100: 1 blue, 13 green, 14 red; 11 green, 11 blue, 7 red; 2 red, 1 blue, 2 green; 10 blue, 15 red");
+
+        assert_eq!(
+            get_game_power(&game, &parse_bag(DEFAULT_BAG)),
+            parse_bag("15 red, 13 green, 11 blue")
+        );
+    }
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn command_after_flags() {
+        assert_eq!(
+            positional_args(&args("--bag 5_red explain")),
+            vec!["explain"]
+        );
+        assert_eq!(
+            positional_args(&args("query --count red>3 --input games.txt")),
+            vec!["query", "red>3"]
+        );
+        assert!(positional_args(&args("--seed 4 --every-colour")).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn unknown_flag() {
+        positional_args(&args("--bga 5_red explain"));
+    }
+
+    #[test]
+    fn repeated_colours_are_separate_draws() {
+        let game = parse_game("Game 1: 7 red, 7 red");
+        let bag = parse_bag(DEFAULT_BAG);
+
+        assert!(Query::Possible.matches(&game, &bag));
+        assert_eq!(
+            get_game_power(&game, &bag),
+            parse_bag("7 red, 0 green, 0 blue")
+        );
+    }
+
+    #[test]
+    fn rounds_are_kept() {
+        let game = parse_game("Game 3: 1 blue, 2 red; 4 green; 3 red, 2 red, 1 blue");
+
+        assert_eq!(game.id, 3);
+        assert_eq!(
+            game.rounds,
             vec![
-                Blue(1),
-                Green(13), // highest green
-                Red(14),
-                Green(11),
-                Blue(11), // highest blue
-                Red(7),
-                Red(2),
-                Blue(1),
-                Green(2),
-                Blue(10),
-                Red(15), // highest red
-            ],
+                parse_round("1 blue, 2 red"),
+                parse_round("4 green"),
+                parse_round("3 red, 2 red, 1 blue"),
+            ]
         );
-        assert_eq!(get_game_power(game), (15, 13, 11));
+        assert_eq!(game.draws().count(), 6);
+        assert_eq!(game.rounds[2].counts(), parse_bag("5 red, 1 blue"));
+        assert_eq!(game.rounds[2].total(), 6);
+        assert!(game.rounds[2].repeats_colour() && !game.rounds[0].repeats_colour());
+    }
+
+    #[test]
+    fn custom_bag() {
+        let bag = parse_bag("5 purple, 3 orange");
+        let game = parse_game("Game 7: 5 purple, 1 orange; 2 orange");
+
+        assert!(game.draws().all(|d| draw_is_possible(d, &bag)));
+        assert!(!draw_is_possible(&draw(4, "orange"), &bag));
+        assert!(!draw_is_possible(&draw(1, "red"), &bag));
+        assert_eq!(get_game_power(&game, &bag), parse_bag("5 purple, 2 orange"));
+    }
+
+    #[test]
+    fn unseen_colour_power() {
+        let game = parse_game("Game 1: 3 red; 4 blue");
+        let maxes = get_game_power(&game, &parse_bag(DEFAULT_BAG));
+
+        assert_eq!(maxes["green"], 0);
+        assert_eq!(maxes.values().product::<i32>(), 0);
     }
 }
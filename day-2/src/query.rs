@@ -0,0 +1,348 @@
+// A small filter language over parsed games, e.g.
+//
+//   red > 10 and blue <= 3
+//   rounds >= 5 or not (power > 1000)
+//   possible and id < 50
+//
+// Comparisons take a field on the left and a whole number on the right, and
+// combine with `and`, `or`, `not` and parentheses (`and` binds tighter than
+// `or`). The fields are:
+//
+//   id        the game's ID
+//   rounds    how many rounds the game had
+//   power     the product of the game's minimum bag, as in part two
+//   <colour>  the most cubes of that colour shown in any one draw (0 if never)
+//
+// and `possible` on its own matches games part one counts for the current bag.
+// A colour can be named on its own if it's in the bag or shows up in a game, so
+// a typo like `rde > 3` is an error rather than a colour nobody drew. Any other
+// colour is written `colour:<name>`.
+
+use std::collections::BTreeSet;
+
+use crate::bag::Bag;
+use crate::{draw_is_possible, get_game_power, Game};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Id,
+    Rounds,
+    Power,
+    Colour(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Compare(Field, Op, i64),
+    Possible,
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+impl Field {
+    pub fn parse(name: &str, colours: &BTreeSet<&str>) -> Result<Field, String> {
+        match name {
+            "id" => Ok(Field::Id),
+            "rounds" => Ok(Field::Rounds),
+            "power" => Ok(Field::Power),
+            colour if colours.contains(colour) => Ok(Field::Colour(colour.to_string())),
+            _ => match name.strip_prefix("colour:") {
+                Some(colour) if !colour.is_empty() => Ok(Field::Colour(colour.to_string())),
+                _ => Err(format!(
+                    "unknown field {name}, expected id, rounds, power, a colour in the bag or games, or colour:<name>"
+                )),
+            },
+        }
+    }
+
+    pub fn value(&self, game: &Game, bag: &Bag) -> i64 {
+        match self {
+            Field::Id => game.id.into(),
+            Field::Rounds => game.rounds.len() as i64,
+            Field::Power => get_game_power(game, bag)
+                .values()
+                .map(|count| i64::from(*count))
+                .product(),
+            Field::Colour(colour) => game
+                .draws()
+                .filter(|draw| draw.colour == *colour)
+                .map(|draw| &draw.count)
+                .max()
+                .map_or(0, |count| i64::from(*count)),
+        }
+    }
+}
+
+impl Op {
+    fn compare(self, left: i64, right: i64) -> bool {
+        match self {
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+        }
+    }
+}
+
+impl Query {
+    pub fn matches(&self, game: &Game, bag: &Bag) -> bool {
+        match self {
+            Query::Compare(field, op, value) => op.compare(field.value(game, bag), *value),
+            Query::Possible => game.draws().all(|d| draw_is_possible(d, bag)),
+            Query::Not(inner) => !inner.matches(game, bag),
+            Query::And(left, right) => left.matches(game, bag) && right.matches(game, bag),
+            Query::Or(left, right) => left.matches(game, bag) || right.matches(game, bag),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(i64),
+    Op(Op),
+    Open,
+    Close,
+}
+
+// The colours that can be used as fields without writing `colour:` first
+pub fn known_colours<'a>(games: &'a [Game], bag: &'a Bag) -> BTreeSet<&'a str> {
+    bag.keys()
+        .map(String::as_str)
+        .chain(
+            games
+                .iter()
+                .flat_map(|g| g.draws().map(|d| d.colour.as_str())),
+        )
+        .collect()
+}
+
+pub fn parse_query(query_str: &str, colours: &BTreeSet<&str>) -> Result<Query, String> {
+    let tokens = tokenise(query_str)?;
+    let mut parser = Parser {
+        tokens,
+        next: 0,
+        colours,
+    };
+    let query = parser.or()?;
+
+    match parser.peek() {
+        None => Ok(query),
+        Some(token) => Err(format!("unexpected {token:?} after a complete query")),
+    }
+}
+
+fn tokenise(query_str: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = query_str.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            ' ' | '\t' => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '<' | '>' | '=' | '!' => {
+                let equals = chars.next_if_eq(&'=').is_some();
+                match (c, equals) {
+                    ('<', false) => Token::Op(Op::Lt),
+                    ('<', true) => Token::Op(Op::Le),
+                    ('>', false) => Token::Op(Op::Gt),
+                    ('>', true) => Token::Op(Op::Ge),
+                    ('=', _) => Token::Op(Op::Eq),
+                    ('!', true) => Token::Op(Op::Ne),
+                    _ => return Err("expected != but found a lone !".to_string()),
+                }
+            }
+            c if c.is_ascii_digit() => {
+                let mut digits = c.to_string();
+                while let Some(d) = chars.next_if(|d| d.is_ascii_digit()) {
+                    digits.push(d);
+                }
+                Token::Number(
+                    digits
+                        .parse()
+                        .map_err(|e| format!("bad number {digits}: {e}"))?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(w) =
+                    chars.next_if(|w| w.is_alphanumeric() || matches!(w, '_' | '-' | ':'))
+                {
+                    word.push(w);
+                }
+                Token::Word(word)
+            }
+            other => return Err(format!("unexpected character {other:?}")),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    next: usize,
+    colours: &'a BTreeSet<&'a str>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if self.peek() == Some(&Token::Word(word.to_string())) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut query = self.and()?;
+        while self.eat_word("or") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut query = self.unary()?;
+        while self.eat_word("and") {
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query, String> {
+        match self.advance() {
+            Some(Token::Word(word)) if word == "not" => Ok(Query::Not(Box::new(self.unary()?))),
+            Some(Token::Word(word)) if word == "possible" => Ok(Query::Possible),
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.advance() {
+                    Some(Token::Close) => Ok(query),
+                    other => Err(format!("expected ) but found {other:?}")),
+                }
+            }
+            Some(Token::Word(field)) => match (self.advance(), self.advance()) {
+                (Some(Token::Op(op)), Some(Token::Number(value))) => Ok(Query::Compare(
+                    Field::parse(&field, self.colours)?,
+                    op,
+                    value,
+                )),
+                (op, value) => Err(format!(
+                    "expected a comparison like `{field} > 3` but found {op:?} {value:?}"
+                )),
+            },
+            other => Err(format!(
+                "expected a comparison, `possible` or ( but found {other:?}"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bag::{parse_bag, DEFAULT_BAG};
+    use crate::{parse_game, sample_games};
+
+    fn matching_ids(query: &str) -> Vec<i32> {
+        let games = sample_games();
+        let bag = parse_bag(DEFAULT_BAG);
+        let query = parse_query(query, &known_colours(&games, &bag)).unwrap();
+
+        games
+            .iter()
+            .filter(|g| query.matches(g, &bag))
+            .map(|g| g.id)
+            .collect()
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(matching_ids("red > 10"), vec![3, 4]);
+        assert_eq!(matching_ids("blue <= 4"), vec![2, 5]);
+        assert_eq!(matching_ids("rounds == 2"), vec![5]);
+        assert_eq!(matching_ids("id != 3"), vec![1, 2, 4, 5]);
+        assert_eq!(matching_ids("colour:purple = 0"), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn field_names() {
+        let games = vec![parse_game("Game 1: 2 teal; 1 red")];
+        let bag = parse_bag("3 red, 4 blue");
+        let colours = known_colours(&games, &bag);
+
+        assert_eq!(Field::parse("power", &colours), Ok(Field::Power));
+        assert_eq!(
+            Field::parse("blue", &colours),
+            Ok(Field::Colour("blue".to_string()))
+        );
+        assert_eq!(
+            Field::parse("teal", &colours),
+            Ok(Field::Colour("teal".to_string()))
+        );
+        assert_eq!(
+            Field::parse("colour:purple", &colours),
+            Ok(Field::Colour("purple".to_string()))
+        );
+        assert!(Field::parse("purple", &colours).is_err());
+        assert!(Field::parse("colour:", &colours).is_err());
+        assert!(parse_query("rde > 3", &colours).is_err());
+    }
+
+    #[test]
+    fn power_and_possible() {
+        // the sample's powers are 48, 12, 1560, 630 and 36
+        assert_eq!(matching_ids("power > 100"), vec![3, 4]);
+        assert_eq!(matching_ids("possible"), vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn combining() {
+        assert_eq!(matching_ids("red > 10 and blue <= 6"), vec![3]);
+        assert_eq!(matching_ids("rounds >= 3 and not possible"), vec![3, 4]);
+        // `and` binds tighter than `or`
+        assert_eq!(matching_ids("id = 1 or id = 2 and red > 100"), vec![1]);
+        assert_eq!(
+            matching_ids("(id = 1 or id = 2) and red > 100"),
+            Vec::<i32>::new()
+        );
+    }
+
+    #[test]
+    fn bad_queries() {
+        let colours = BTreeSet::from(["red", "blue"]);
+
+        assert!(parse_query("red >", &colours).is_err());
+        assert!(parse_query("red > 3 blue", &colours).is_err());
+        assert!(parse_query("(red > 3", &colours).is_err());
+        assert!(parse_query("red ! 3", &colours).is_err());
+        assert!(parse_query("red > 3 & blue < 2", &colours).is_err());
+    }
+}
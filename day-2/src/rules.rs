@@ -0,0 +1,171 @@
+// Variations on part one's rule. Each one is checked on top of the usual
+// per-colour limit, so a game has to be possible in the puzzle's sense before a
+// variation can rule it out.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::bag::Bag;
+use crate::{draw_is_possible, flag_value, Draw, Game};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    // the puzzle's rule: no round shows more of a colour than the bag holds
+    PerColour,
+    // no round shows more than this many cubes in total
+    RoundTotal(i32),
+    // cubes aren't put back between rounds, so a game can't show more of a
+    // colour across all of its rounds than the bag holds
+    NoReplacement,
+    // every colour in the bag is shown at least once during the game
+    EveryColour,
+}
+
+impl Rule {
+    pub fn allows(&self, game: &Game, bag: &Bag) -> bool {
+        game.draws().all(|d| draw_is_possible(d, bag))
+            && match self {
+                Rule::PerColour => true,
+                Rule::RoundTotal(cap) => game.rounds.iter().all(|r| r.total() <= *cap),
+                Rule::NoReplacement => {
+                    let mut totals: BTreeMap<String, i32> = BTreeMap::new();
+                    game.draws().for_each(|d| {
+                        *totals.entry(d.colour.clone()).or_insert(0) += d.count;
+                    });
+                    totals
+                        .into_iter()
+                        .all(|(colour, count)| draw_is_possible(&Draw { colour, count }, bag))
+                }
+                Rule::EveryColour => bag
+                    .keys()
+                    .all(|colour| game.draws().any(|d| d.colour == *colour && d.count > 0)),
+            }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::PerColour => write!(f, "per colour (part one)"),
+            Rule::RoundTotal(cap) => write!(f, "at most {cap} cubes a round"),
+            Rule::NoReplacement => write!(f, "cubes not put back between rounds"),
+            Rule::EveryColour => write!(f, "every colour shown at least once"),
+        }
+    }
+}
+
+// `--round-total <n>`, `--no-replacement` and `--every-colour` pick the rules to
+// compare against part one. With none of them, every rule that doesn't need a
+// number is compared.
+pub fn rules_from_args(args: &[String]) -> Vec<Rule> {
+    let has = |flag: &str| args.iter().any(|a| a == flag);
+    let mut rules = vec![];
+
+    if let Some(cap) = flag_value(args, "--round-total") {
+        rules.push(Rule::RoundTotal(
+            cap.parse().expect("--round-total needs a number of cubes"),
+        ));
+    }
+    if has("--no-replacement") {
+        rules.push(Rule::NoReplacement);
+    }
+    if has("--every-colour") {
+        rules.push(Rule::EveryColour);
+    }
+    if rules.is_empty() {
+        rules = vec![Rule::NoReplacement, Rule::EveryColour];
+    }
+
+    rules
+}
+
+// The sum of the IDs of the games the rules allow
+pub fn part_one_under(rules: &[Rule], games: &[Game], bag: &Bag) -> i32 {
+    games
+        .iter()
+        .filter(|game| rules.iter().all(|rule| rule.allows(game, bag)))
+        .map(|game| game.id)
+        .sum()
+}
+
+pub fn rules_report(rules: &[Rule], games: &[Game], bag: &Bag) -> String {
+    let mut report = format!(
+        "{}: {}\n",
+        Rule::PerColour,
+        part_one_under(&[Rule::PerColour], games, bag)
+    );
+
+    for rule in rules {
+        report += &format!(
+            "{rule}: {}\n",
+            part_one_under(std::slice::from_ref(rule), games, bag)
+        );
+    }
+    if rules.len() > 1 {
+        report += &format!("all of the above: {}\n", part_one_under(rules, games, bag));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bag::{parse_bag, DEFAULT_BAG};
+    use crate::{parse_game, sample_games};
+
+    fn allowed(rule: Rule, game: &str) -> bool {
+        rule.allows(&parse_game(game), &parse_bag(DEFAULT_BAG))
+    }
+
+    #[test]
+    fn round_total() {
+        let game = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green";
+
+        assert!(allowed(Rule::RoundTotal(9), game));
+        assert!(!allowed(Rule::RoundTotal(8), game));
+        // the per-colour limit still applies underneath
+        assert!(!allowed(Rule::RoundTotal(100), "Game 2: 13 red"));
+    }
+
+    #[test]
+    fn no_replacement() {
+        assert!(allowed(
+            Rule::NoReplacement,
+            "Game 1: 6 red; 6 red; 14 blue"
+        ));
+        assert!(!allowed(Rule::NoReplacement, "Game 1: 6 red; 7 red"));
+        assert!(allowed(Rule::PerColour, "Game 1: 6 red; 7 red"));
+    }
+
+    #[test]
+    fn every_colour() {
+        assert!(allowed(Rule::EveryColour, "Game 1: 1 red; 1 green, 1 blue"));
+        assert!(!allowed(Rule::EveryColour, "Game 1: 1 red; 1 green"));
+    }
+
+    #[test]
+    fn report_per_rule() {
+        let games = sample_games();
+        let rules = vec![Rule::RoundTotal(9), Rule::NoReplacement];
+
+        assert_eq!(
+            rules_report(&rules, &games, &parse_bag(DEFAULT_BAG)),
+            "per colour (part one): 8\nat most 9 cubes a round: 3\ncubes not put back between rounds: 8\nall of the above: 3\n"
+        );
+    }
+
+    #[test]
+    fn rule_args() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            rules_from_args(&args(&["rules", "--every-colour", "--round-total", "20"])),
+            vec![Rule::RoundTotal(20), Rule::EveryColour]
+        );
+        assert_eq!(
+            rules_from_args(&args(&["rules"])),
+            vec![Rule::NoReplacement, Rule::EveryColour]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+// A first look at a new input: how many cubes of each colour get shown, how the
+// colours move together, and how the game powers from part two are spread out.
+//
+// A colour's counts are taken from the rounds that showed it. Correlations
+// compare every round, counting a colour the round didn't show as 0. A colour
+// listed more than once in a round is added up for that round.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::bag::Bag;
+use crate::query::Field;
+use crate::Game;
+
+const BAR_WIDTH: usize = 40;
+const MAX_BINS: usize = 12;
+
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub min: i64,
+    pub max: i64,
+    pub mean: f64,
+    pub median: f64,
+}
+
+// (lowest value, highest value, how many values fell in between)
+pub type Bin = (i64, i64, usize);
+
+pub fn summarise(values: &[i64]) -> Option<Summary> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) as f64 / 2.0
+    } else {
+        sorted[middle] as f64
+    };
+
+    Some(Summary {
+        min: sorted[0],
+        max: sorted[sorted.len() - 1],
+        mean: sorted.iter().sum::<i64>() as f64 / sorted.len() as f64,
+        median,
+    })
+}
+
+// Splits the values into at most max_bins equal width bins covering min to max.
+// Small ranges get one bin per value.
+pub fn histogram(values: &[i64], max_bins: usize) -> Vec<Bin> {
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return vec![];
+    };
+    let span = max - min + 1;
+    let width = (span + max_bins as i64 - 1) / max_bins as i64;
+
+    (0..(span + width - 1) / width)
+        .map(|i| {
+            let low = min + i * width;
+            let high = (low + width - 1).min(*max);
+            let count = values.iter().filter(|v| (low..=high).contains(v)).count();
+            (low, high, count)
+        })
+        .collect()
+}
+
+pub fn render_histogram(bins: &[Bin]) -> String {
+    let most = bins.iter().map(|(_, _, count)| *count).max().unwrap_or(0);
+    let labels: Vec<String> = bins
+        .iter()
+        .map(|(low, high, _)| match low == high {
+            true => low.to_string(),
+            false => format!("{low}-{high}"),
+        })
+        .collect();
+    let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+
+    bins.iter()
+        .zip(&labels)
+        .fold(String::new(), |mut chart, ((_, _, count), label)| {
+            let bar = "█".repeat((count * BAR_WIDTH).div_ceil(most.max(1)));
+            writeln!(chart, "    {label:>label_width$} | {bar} {count}").unwrap();
+            chart
+        })
+}
+
+// Pearson's correlation, or None when either side never changes
+pub fn correlation(xs: &[i64], ys: &[i64]) -> Option<f64> {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<i64>() as f64 / n;
+    let mean_y = ys.iter().sum::<i64>() as f64 / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+
+    for (x, y) in xs.iter().zip(ys) {
+        let (dx, dy) = (*x as f64 - mean_x, *y as f64 - mean_y);
+        cov += dx * dy;
+        var_x += dx * dx;
+        var_y += dy * dy;
+    }
+
+    (var_x > 0.0 && var_y > 0.0).then(|| cov / (var_x * var_y).sqrt())
+}
+
+pub fn stats_report(games: &[Game], bag: &Bag) -> String {
+    let colours: BTreeSet<&String> = games
+        .iter()
+        .flat_map(|g| g.draws().map(|d| &d.colour))
+        .collect();
+    let per_round: BTreeMap<&String, Vec<i64>> = colours
+        .iter()
+        .map(|colour| {
+            let counts = games
+                .iter()
+                .flat_map(|g| &g.rounds)
+                .map(|round| round.counts().get(*colour).map_or(0, |c| i64::from(*c)))
+                .collect();
+            (*colour, counts)
+        })
+        .collect();
+
+    let mut report = String::new();
+    let rounds: usize = games.iter().map(|g| g.rounds.len()).sum();
+    writeln!(report, "{} games, {rounds} rounds", games.len()).unwrap();
+    let repeats = games
+        .iter()
+        .flat_map(|g| &g.rounds)
+        .filter(|round| round.repeats_colour())
+        .count();
+    if repeats > 0 {
+        writeln!(report, "{repeats} rounds list a colour more than once").unwrap();
+    }
+
+    for (colour, counts) in &per_round {
+        let shown: Vec<i64> = counts.iter().copied().filter(|c| *c > 0).collect();
+        if let Some(summary) = summarise(&shown) {
+            writeln!(
+                report,
+                "\n{colour}: shown in {} rounds, min {}, max {}, mean {:.2}, median {}",
+                shown.len(),
+                summary.min,
+                summary.max,
+                summary.mean,
+                summary.median
+            )
+            .unwrap();
+            report += &render_histogram(&histogram(&shown, MAX_BINS));
+        }
+    }
+
+    writeln!(report, "\nCorrelation between colours, round by round:").unwrap();
+    let names: Vec<&&String> = per_round.keys().collect();
+    for (i, a) in names.iter().enumerate() {
+        for b in &names[i + 1..] {
+            let value = correlation(&per_round[*a], &per_round[*b])
+                .map_or("n/a".to_string(), |r| format!("{r:+.3}"));
+            writeln!(report, "    {a} ~ {b}: {value}").unwrap();
+        }
+    }
+
+    let powers: Vec<i64> = games.iter().map(|g| Field::Power.value(g, bag)).collect();
+    if let Some(summary) = summarise(&powers) {
+        writeln!(
+            report,
+            "\nGame powers: min {}, max {}, mean {:.2}, median {}",
+            summary.min, summary.max, summary.mean, summary.median
+        )
+        .unwrap();
+        report += &render_histogram(&histogram(&powers, MAX_BINS));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bag::{parse_bag, DEFAULT_BAG};
+    use crate::{parse_game, sample_games};
+
+    #[test]
+    fn summaries() {
+        assert_eq!(
+            summarise(&[4, 1, 3, 2]),
+            Some(Summary {
+                min: 1,
+                max: 4,
+                mean: 2.5,
+                median: 2.5,
+            })
+        );
+        assert_eq!(summarise(&[5, 1, 9]).unwrap().median, 5.0);
+        assert_eq!(summarise(&[]), None);
+    }
+
+    #[test]
+    fn bins() {
+        assert_eq!(
+            histogram(&[1, 2, 2, 3], 12),
+            vec![(1, 1, 1), (2, 2, 2), (3, 3, 1)]
+        );
+        assert_eq!(histogram(&[0, 5, 9, 10], 2), vec![(0, 5, 2), (6, 10, 2)]);
+        assert_eq!(histogram(&[7], 12), vec![(7, 7, 1)]);
+        assert!(histogram(&[], 12).is_empty());
+    }
+
+    #[test]
+    fn bars() {
+        let chart = render_histogram(&[(1, 1, 2), (2, 3, 1)]);
+
+        assert_eq!(
+            chart,
+            format!(
+                "      1 | {} 2\n    2-3 | {} 1\n",
+                "█".repeat(40),
+                "█".repeat(20)
+            )
+        );
+    }
+
+    #[test]
+    fn correlations() {
+        assert!((correlation(&[1, 2, 3], &[2, 4, 6]).unwrap() - 1.0).abs() < 1e-12);
+        assert!((correlation(&[1, 2, 3], &[3, 2, 1]).unwrap() + 1.0).abs() < 1e-12);
+        assert_eq!(correlation(&[1, 1, 1], &[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn report() {
+        let games = &sample_games()[..2];
+        let report = stats_report(games, &parse_bag(DEFAULT_BAG));
+
+        assert!(report.starts_with("2 games, 6 rounds\n\n"));
+        assert!(report.contains("\nblue: shown in 5 rounds, min 1, max 6, mean 3.00, median 3\n"));
+        assert!(report.contains("    blue ~ green: "));
+        assert!(report.contains("\nGame powers: min 12, max 48, mean 30.00, median 30\n"));
+
+        let repeated = stats_report(&[parse_game("Game 3: 2 red, 3 red; 1 red")], &Bag::new());
+        assert!(repeated.starts_with("1 games, 2 rounds\n1 rounds list a colour more than once\n"));
+        assert!(repeated.contains("\nred: shown in 2 rounds, min 1, max 5, mean 3.00, median 3\n"));
+    }
+}
@@ -0,0 +1,153 @@
+// What each kind of symbol is attached to. Every symbol character gets the part
+// numbers touching any cell holding it, counted once each however many of those
+// cells they touch, along with their sum and product. Numbers touching more than
+// one symbol cell are listed separately, since they're counted under every symbol
+// they touch.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{EngineDiagram, NumberSpan};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolSummary {
+    pub symbol: char,
+    pub parts: usize,
+    pub sum: u128,
+    // None when the product doesn't fit in a u128
+    pub product: Option<u128>,
+    // log10 of the product, for telling how big an overflowing product is
+    pub product_log10: f64,
+}
+
+// `touching` maps symbol cells to the indexes of the numbers touching them, as
+// built by touching_symbols
+pub fn summarise_symbols(
+    diagram: &EngineDiagram,
+    numbers: &[NumberSpan],
+    touching: &BTreeMap<(usize, usize), Vec<usize>>,
+) -> Vec<SymbolSummary> {
+    let mut by_symbol: BTreeMap<char, BTreeSet<usize>> = BTreeMap::new();
+    for ((row, col), touched) in touching {
+        by_symbol
+            .entry(diagram.at(*row, *col))
+            .or_default()
+            .extend(touched);
+    }
+
+    by_symbol
+        .into_iter()
+        .map(|(symbol, touched)| {
+            let mut values = touched.iter().map(|i| numbers[*i].value);
+            SymbolSummary {
+                symbol,
+                parts: touched.len(),
+                sum: values.clone().map(u128::from).sum(),
+                product_log10: values.clone().map(|v| (v as f64).log10()).sum(),
+                product: values.try_fold(1_u128, |product, v| product.checked_mul(u128::from(v))),
+            }
+        })
+        .collect()
+}
+
+// The indexes of numbers touching more than one symbol cell, with how many they
+// touch
+pub fn shared_numbers(touching: &BTreeMap<(usize, usize), Vec<usize>>) -> Vec<(usize, usize)> {
+    let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+    touching
+        .values()
+        .flatten()
+        .for_each(|i| *counts.entry(*i).or_insert(0) += 1);
+
+    counts.into_iter().filter(|(_, count)| *count > 1).collect()
+}
+
+pub fn aggregate_report(
+    diagram: &EngineDiagram,
+    numbers: &[NumberSpan],
+    touching: &BTreeMap<(usize, usize), Vec<usize>>,
+) -> String {
+    let mut report = String::new();
+
+    for summary in summarise_symbols(diagram, numbers, touching) {
+        let product = summary
+            .product
+            .map_or(format!("about 10^{:.0}", summary.product_log10), |p| {
+                p.to_string()
+            });
+        report.push_str(&format!(
+            "{}: touches {} part numbers, sum {}, product {product}\n",
+            summary.symbol, summary.parts, summary.sum
+        ));
+    }
+
+    let shared = shared_numbers(touching);
+    report.push_str(&format!(
+        "{} numbers touch more than one symbol\n",
+        shared.len()
+    ));
+    for (i, count) in shared {
+        let number = &numbers[i];
+        report.push_str(&format!(
+            "  {} at row {}, column {} touches {count} symbols\n",
+            number.value,
+            number.row + 1,
+            number.start + 1
+        ));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neighbourhood::Neighbourhood;
+    use crate::{find_numbers, is_symbol, touching_symbols};
+
+    #[test]
+    fn per_symbol() {
+        let diagram = EngineDiagram::new("2*3.\n..#.\n.4..\n....\n9..5");
+        let numbers = find_numbers(&diagram);
+        let symbols = diagram.mask_of(is_symbol);
+        let touching = touching_symbols(&symbols, &numbers, &Neighbourhood::Eight.offsets());
+
+        assert_eq!(
+            summarise_symbols(&diagram, &numbers, &touching),
+            vec![
+                SymbolSummary {
+                    symbol: '#',
+                    parts: 2,
+                    sum: 7,
+                    product: Some(12),
+                    product_log10: 12_f64.log10(),
+                },
+                SymbolSummary {
+                    symbol: '*',
+                    parts: 2,
+                    sum: 5,
+                    product: Some(6),
+                    product_log10: 6_f64.log10(),
+                },
+            ]
+        );
+        // the 3 touches both the * and the #
+        assert_eq!(shared_numbers(&touching), vec![(1, 2)]);
+        assert!(aggregate_report(&diagram, &numbers, &touching).ends_with(
+            "1 numbers touch more than one symbol\n  3 at row 1, column 3 touches 2 symbols\n"
+        ));
+    }
+
+    #[test]
+    fn huge_product() {
+        let row = "999999.".repeat(8);
+        let diagram = EngineDiagram::new(&format!("{row}\n{}", "@".repeat(row.len())));
+        let numbers = find_numbers(&diagram);
+        let symbols = diagram.mask_of(is_symbol);
+        let touching = touching_symbols(&symbols, &numbers, &Neighbourhood::Eight.offsets());
+        let summary = &summarise_symbols(&diagram, &numbers, &touching)[0];
+
+        assert_eq!((summary.parts, summary.sum), (8, 8 * 999999));
+        assert_eq!(summary.product, None);
+        assert_eq!(summary.product_log10.round(), 48.0);
+    }
+}
@@ -0,0 +1,377 @@
+// A schematic that can be edited a cell at a time, keeping both answers up to date.
+//
+// Changing one cell can only affect things near it: the numbers on its row that
+// it joins, splits or changes (the span), the numbers within reach of it, and the
+// *s within reach of the span. So every number and * within reach of the span has
+// its share of the totals taken off before the edit and added back after, and
+// nothing else is looked at.
+//
+// This is meant for big grids, so cells are stored a byte each (validation has
+// made sure they're all ASCII) and the number covering each cell as a 4 byte ID.
+
+use std::collections::{BTreeSet, HashMap};
+use std::num::NonZeroU32;
+
+use crate::neighbourhood::Neighbourhood;
+use crate::symbols::Classifier;
+use crate::validate::is_cell;
+use crate::{find_numbers, EngineDiagram, NumberSpan};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Totals {
+    pub part_sum: u128,
+    pub ratio_sum: u128,
+}
+
+type NumberId = NonZeroU32;
+
+pub struct Schematic {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+    classifier: Classifier,
+    reach: Vec<(isize, isize)>,
+    numbers: HashMap<NumberId, NumberSpan>,
+    // which number, by its key in `numbers`, covers each cell
+    number_at: Vec<Option<NumberId>>,
+    next_id: u32,
+    totals: Totals,
+}
+
+impl Schematic {
+    pub fn new(
+        diagram: &EngineDiagram,
+        classifier: Classifier,
+        neighbourhood: Neighbourhood,
+    ) -> Self {
+        let (width, height) = (diagram.width, diagram.height());
+        let mut schematic = Schematic {
+            width,
+            height,
+            cells: diagram.cells.bytes().collect(),
+            classifier,
+            reach: neighbourhood.offsets(),
+            numbers: HashMap::new(),
+            number_at: vec![None; width * height],
+            next_id: 1,
+            totals: Totals {
+                part_sum: 0,
+                ratio_sum: 0,
+            },
+        };
+
+        find_numbers(diagram)
+            .into_iter()
+            .for_each(|number| schematic.insert_number(number));
+        schematic.totals = Totals {
+            part_sum: schematic
+                .numbers
+                .values()
+                .filter(|number| schematic.is_part(number))
+                .map(|number| u128::from(number.value))
+                .sum(),
+            ratio_sum: (0..height)
+                .flat_map(|row| (0..width).map(move |col| (row, col)))
+                .filter_map(|(row, col)| schematic.gear_ratio(row, col))
+                .sum(),
+        };
+
+        schematic
+    }
+
+    #[cfg(test)]
+    pub fn totals(&self) -> Totals {
+        self.totals
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> char {
+        char::from(self.cells[row * self.width + col])
+    }
+
+    // Changes one cell and returns the new totals. An edit that would make a
+    // number too big for a u64 is refused, leaving the schematic as it was.
+    pub fn set(&mut self, row: usize, col: usize, c: char) -> Result<Totals, String> {
+        if row >= self.height || col >= self.width {
+            return Err(format!(
+                "row {}, column {} is outside the schematic",
+                row + 1,
+                col + 1
+            ));
+        }
+        // the same rule validation uses, so the blank can be a space
+        if !is_cell(c, &self.classifier) {
+            return Err(format!("{c:?} can't be a cell"));
+        }
+        let byte = c as u8;
+
+        // the numbers the edit can join, split or change are the ones covering
+        // the cell or either side of it
+        let touched: BTreeSet<NumberId> = [col.checked_sub(1), Some(col), Some(col + 1)]
+            .into_iter()
+            .flatten()
+            .filter(|c| *c < self.width)
+            .filter_map(|c| self.number_at[row * self.width + c])
+            .collect();
+        let start = touched
+            .iter()
+            .map(|id| self.numbers[id].start)
+            .fold(col, usize::min);
+        let end = touched
+            .iter()
+            .map(|id| self.numbers[id].end)
+            .fold(col + 1, usize::max);
+
+        let mut new_row = self.cells[row * self.width..(row + 1) * self.width].to_vec();
+        new_row[col] = byte;
+        let found = numbers_in(&new_row, row, start, end)?;
+
+        let nearby: BTreeSet<(usize, usize)> =
+            (start..end).flat_map(|c| self.in_reach(row, c)).collect();
+
+        self.add_shares(&nearby, -1);
+        touched.iter().for_each(|id| self.remove_number(*id));
+        self.cells[row * self.width + col] = byte;
+        found
+            .into_iter()
+            .for_each(|number| self.insert_number(number));
+        self.add_shares(&nearby, 1);
+
+        Ok(self.totals)
+    }
+
+    fn in_reach(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.reach.iter().filter_map(move |(dr, dc)| {
+            let r = row.checked_add_signed(*dr).filter(|r| *r < self.height)?;
+            let c = col.checked_add_signed(*dc).filter(|c| *c < self.width)?;
+            Some((r, c))
+        })
+    }
+
+    // Adds (or with sign -1, removes) what the numbers and *s in these cells add
+    // to the totals
+    fn add_shares(&mut self, cells: &BTreeSet<(usize, usize)>, sign: i64) {
+        let numbers: BTreeSet<NumberId> = cells
+            .iter()
+            .filter_map(|(row, col)| self.number_at[row * self.width + col])
+            .collect();
+        let parts: u128 = numbers
+            .iter()
+            .map(|id| &self.numbers[id])
+            .filter(|number| self.is_part(number))
+            .map(|number| u128::from(number.value))
+            .sum();
+        let ratios: u128 = cells
+            .iter()
+            .filter_map(|(row, col)| self.gear_ratio(*row, *col))
+            .sum();
+
+        let apply = |total: &mut u128, share: u128| {
+            *total = if sign > 0 {
+                *total + share
+            } else {
+                *total - share
+            }
+        };
+        apply(&mut self.totals.part_sum, parts);
+        apply(&mut self.totals.ratio_sum, ratios);
+    }
+
+    fn is_part(&self, number: &NumberSpan) -> bool {
+        (number.start..number.end).any(|col| {
+            self.in_reach(number.row, col)
+                .any(|(r, c)| self.classifier.is_symbol(self.get(r, c)))
+        })
+    }
+
+    fn gear_ratio(&self, row: usize, col: usize) -> Option<u128> {
+        let c = self.get(row, col);
+        if c != '*' || !self.classifier.is_symbol(c) {
+            return None;
+        }
+
+        let touching: BTreeSet<NumberId> = self
+            .in_reach(row, col)
+            .filter_map(|(r, c)| self.number_at[r * self.width + c])
+            .collect();
+        match touching.iter().collect::<Vec<_>>()[..] {
+            [first, second] => {
+                Some(u128::from(self.numbers[first].value) * u128::from(self.numbers[second].value))
+            }
+            _ => None,
+        }
+    }
+
+    fn insert_number(&mut self, number: NumberSpan) {
+        let id = NumberId::new(self.next_id).unwrap();
+        self.next_id = self
+            .next_id
+            .checked_add(1)
+            .expect("ran out of number IDs after billions of edits");
+
+        let row_start = number.row * self.width;
+        self.number_at[row_start + number.start..row_start + number.end].fill(Some(id));
+        self.numbers.insert(id, number);
+    }
+
+    fn remove_number(&mut self, id: NumberId) {
+        let number = self.numbers.remove(&id).unwrap();
+        let row_start = number.row * self.width;
+        self.number_at[row_start + number.start..row_start + number.end].fill(None);
+    }
+}
+
+// Finds the numbers in columns start..end of a row. The cells either side of that
+// range mustn't be digits, so no number runs past it.
+fn numbers_in(
+    row_cells: &[u8],
+    row: usize,
+    start: usize,
+    end: usize,
+) -> Result<Vec<NumberSpan>, String> {
+    let mut found = vec![];
+    let mut col = start;
+
+    while col < end {
+        if !row_cells[col].is_ascii_digit() {
+            col += 1;
+            continue;
+        }
+        let digits_end = (col..end)
+            .find(|c| !row_cells[*c].is_ascii_digit())
+            .unwrap_or(end);
+        let digits = std::str::from_utf8(&row_cells[col..digits_end]).unwrap();
+        let value = digits.parse().map_err(|_| {
+            format!(
+                "the edit would make a {} digit number at row {}, column {}, too big for a u64",
+                digits.len(),
+                row + 1,
+                col + 1
+            )
+        })?;
+        found.push(NumberSpan {
+            value,
+            row,
+            start: col,
+            end: digits_end,
+        });
+        col = digits_end;
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::parse_symbol_set;
+    use crate::{find_gears, touches_mask, Gear};
+
+    // Both answers worked out from scratch, the way main does it
+    fn solve(schematic: &Schematic) -> Totals {
+        let text: Vec<String> = schematic
+            .cells
+            .chunks(schematic.width)
+            .map(|row| String::from_utf8(row.to_vec()).unwrap())
+            .collect();
+        let diagram = EngineDiagram::new(&text.join("\n"));
+        let classifier = &schematic.classifier;
+        let mask = diagram
+            .mask_of(|c| classifier.is_symbol(c))
+            .dilate(&schematic.reach);
+        let numbers = find_numbers(&diagram);
+
+        Totals {
+            part_sum: numbers
+                .iter()
+                .filter(|n| touches_mask(n, &mask))
+                .map(|n| u128::from(n.value))
+                .sum(),
+            ratio_sum: find_gears(&diagram, &numbers, classifier, &schematic.reach)
+                .iter()
+                .map(Gear::ratio)
+                .sum(),
+        }
+    }
+
+    #[test]
+    fn example_edits() {
+        let diagram = EngineDiagram::new(include_str!("../input_easier.txt"));
+        let mut schematic = Schematic::new(&diagram, Classifier::default(), Neighbourhood::Eight);
+
+        assert_eq!(
+            schematic.totals(),
+            Totals {
+                part_sum: 4361,
+                ratio_sum: 467835,
+            }
+        );
+
+        // removing the * under 467 leaves 467 and 35 on their own
+        let totals = schematic.set(1, 3, '.').unwrap();
+        assert_eq!(totals.part_sum, 4361 - 467 - 35);
+        assert_eq!(totals.ratio_sum, 755 * 598);
+
+        // filling the gap between 467 and 114 joins them into 46700114
+        schematic.set(1, 3, '*').unwrap();
+        schematic.set(0, 3, '0').unwrap();
+        let totals = schematic.set(0, 4, '0').unwrap();
+        assert_eq!(totals.part_sum, 4361 - 467 + 46700114);
+        assert_eq!(totals, solve(&schematic));
+    }
+
+    #[test]
+    fn random_edits_match_full_solve() {
+        let diagram = EngineDiagram::new(include_str!("../input_easier.txt"));
+        let choices = ['.', '.', '.', '*', '#', '1', '7', '9'];
+
+        for neighbourhood in [Neighbourhood::Eight, Neighbourhood::Manhattan(2)] {
+            let mut schematic = Schematic::new(&diagram, Classifier::default(), neighbourhood);
+            let mut state = 12345_u64;
+            let mut next = |n: usize| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 33) as usize % n
+            };
+
+            for _ in 0..500 {
+                let (row, col, c) = (next(10), next(10), choices[next(choices.len())]);
+                assert_eq!(schematic.set(row, col, c), Ok(solve(&schematic)));
+            }
+        }
+    }
+
+    #[test]
+    fn refused_edits() {
+        let diagram = EngineDiagram::new("9999999999.9999999999\n*....................");
+        let mut schematic = Schematic::new(&diagram, Classifier::default(), Neighbourhood::Eight);
+        let before = schematic.totals();
+
+        // joining the two numbers would make a 21 digit number
+        assert!(schematic.set(0, 10, '9').is_err());
+        assert_eq!(schematic.get(0, 10), '.');
+        assert_eq!(schematic.totals(), before);
+        assert!(schematic.set(2, 0, '*').is_err());
+        assert!(schematic.set(0, 0, '\n').is_err());
+
+        assert_eq!(schematic.set(1, 11, '*'), Ok(solve(&schematic)));
+    }
+
+    #[test]
+    fn space_as_the_blank() {
+        let classifier = Classifier {
+            symbols: parse_symbol_set("*").unwrap(),
+            blank: ' ',
+        };
+        let diagram = EngineDiagram::new("12 *\n   3");
+        let mut schematic = Schematic::new(&diagram, classifier, Neighbourhood::Eight);
+
+        assert_eq!(schematic.totals().part_sum, 3);
+        assert_eq!(schematic.set(0, 2, ' '), Ok(solve(&schematic)));
+        assert_eq!(schematic.set(1, 3, ' '), Ok(solve(&schematic)));
+        assert_eq!(schematic.totals().part_sum, 0);
+        assert!(schematic.set(0, 0, '\t').is_err());
+        // only * is a symbol, so a letter would be neither a symbol nor filler
+        assert!(schematic.set(0, 0, 'a').is_err());
+    }
+}
@@ -1,65 +1,361 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
-struct EngineDiagram(String);
+use aggregate::aggregate_report;
+use editor::Schematic;
+use mask::Mask;
+use neighbourhood::Neighbourhood;
+use render::{render_ansi, write_image, CellMap};
+use symbols::{parse_symbol_set, symbol_counts, symbol_report, Classifier};
+use validate::{validate, Problem};
+
+mod aggregate;
+mod editor;
+mod mask;
+mod neighbourhood;
+mod render;
+mod symbols;
+mod validate;
+
+// The schematic's rows joined into one string, along with how wide each row is
+struct EngineDiagram {
+    cells: String,
+    width: usize,
+}
+
+impl EngineDiagram {
+    fn new(input: &str) -> Self {
+        EngineDiagram {
+            cells: input.lines().collect::<Vec<&str>>().join(""),
+            width: input.lines().next().map_or(0, str::len),
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len().checked_div(self.width).unwrap_or(0)
+    }
+
+    fn at(&self, row: usize, col: usize) -> char {
+        self.cells[row * self.width + col..].chars().next().unwrap()
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &str> {
+        (0..self.height()).map(|r| &self.cells[r * self.width..(r + 1) * self.width])
+    }
+
+    // 1s wherever a cell passes the test
+    fn mask_of(&self, test: impl Fn(char) -> bool) -> Mask {
+        let mut mask = Mask::new(self.width, self.height());
+        for (row, cells) in self.rows().enumerate() {
+            for (col, c) in cells.chars().enumerate() {
+                if test(c) {
+                    mask.set(row, col, true);
+                }
+            }
+        }
+        mask
+    }
+}
 
 impl fmt::Binary for EngineDiagram {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let val = &self.0;
+        fmt::Binary::fmt(&self.mask_of(is_symbol), f)
+    }
+}
 
-        let binary_str = val
-            .chars()
-            .map(|c| match is_symbol(c) {
-                true => '1',
-                false => '0',
-            })
-            .collect::<String>();
+#[derive(Debug, Clone, PartialEq)]
+struct NumberSpan {
+    value: u64,
+    row: usize,
+    // columns the number covers, end exclusive
+    start: usize,
+    end: usize,
+}
 
-        fmt::Display::fmt(&binary_str, f)
+#[derive(Debug, Clone, PartialEq)]
+struct Gear {
+    row: usize,
+    col: usize,
+    parts: (u64, u64),
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    classifier: Classifier,
+    neighbourhood: Neighbourhood,
+    gears: bool,
+    symbol_report: bool,
+    // what every kind of symbol touches, like part two does for gears
+    per_symbol: bool,
+    // print the schematic in colour
+    show: bool,
+    // an image file to draw the schematic to, and how many pixels wide each cell is
+    render_path: Option<String>,
+    scale: usize,
+    // cells to change after solving, as (row, column, new char) counting from 1
+    edits: Vec<(usize, usize, char)>,
+}
+
+impl Gear {
+    fn ratio(&self) -> u128 {
+        u128::from(self.parts.0) * u128::from(self.parts.1)
     }
 }
 
 fn main() {
-    let input = include_str!("../input_easier.txt");
-    let width = input.lines().next().unwrap().len();
+    let options = parse_args(std::env::args().skip(1));
+    let classifier = &options.classifier;
+    let reach = options.neighbourhood.offsets();
+    let input = include_str!("../input.txt");
+
+    let (warnings, problems): (Vec<_>, Vec<_>) = validate(input, classifier)
+        .into_iter()
+        .partition(Problem::is_warning);
+    warnings
+        .iter()
+        .for_each(|warning| eprintln!("Warning: {warning}"));
+    if !problems.is_empty() {
+        let problems: Vec<String> = problems.iter().map(Problem::to_string).collect();
+        panic!("the schematic isn't valid:\n{}", problems.join("\n"));
+    }
+
+    if options.symbol_report {
+        print!("{}", symbol_report(&symbol_counts(input, classifier)));
+    }
 
     // 1. create a string of the puzzle chars
-    let no_newlines = EngineDiagram(input.lines().collect::<Vec<&str>>().join(""));
+    let no_newlines = EngineDiagram::new(input);
     // 2. mask out everything that isn't a symbol with 0s, symbols with 1s
-    let bin_str_symbols = format!("{no_newlines:b}");
-    // 3. replace all 0s offset from all 1s by original width of puzzle with 1s (in both directions)
-    // 4. replace all 0s on either side of all 1s with 1s
-    let mask = rotated_or_str(&rotated_or_str(&bin_str_symbols, width), 1);
+    let symbols = no_newlines.mask_of(|c| classifier.is_symbol(c));
+    // 3. and 4. mark every cell in reach of a symbol, which by default is the 8
+    // cells around it
+    let mask = symbols.dilate(&reach);
     // 5. test all numbers in puzzle to see if they touch a 1 in the mask
+    let numbers = find_numbers(&no_newlines);
+    let part_numbers = numbers.iter().filter(|n| touches_mask(n, &mask));
     // 6. sum all numbers that pass that test
+    let part_number_sum: u128 = part_numbers.map(|n| u128::from(n.value)).sum();
+
+    println!("Problem 1: The sum of all the part numbers is {part_number_sum}");
+
+    let gears = find_gears(&no_newlines, &numbers, classifier, &reach);
+    let ratio_sum: u128 = gears.iter().map(Gear::ratio).sum();
 
-    println!("Size is {width}");
-    println!("{mask}");
+    if options.gears {
+        gears.iter().for_each(|g| {
+            println!(
+                "Gear at row {}, column {}: {} * {} = {}",
+                g.row + 1,
+                g.col + 1,
+                g.parts.0,
+                g.parts.1,
+                g.ratio()
+            )
+        });
+    }
+    println!("Problem 2: The sum of all the gear ratios is {ratio_sum}");
+
+    if options.per_symbol {
+        let touching = touching_symbols(&symbols, &numbers, &reach);
+        print!("{}", aggregate_report(&no_newlines, &numbers, &touching));
+    }
+
+    if !options.edits.is_empty() {
+        let mut schematic = Schematic::new(&no_newlines, classifier.clone(), options.neighbourhood);
+        for (row, col, c) in &options.edits {
+            match schematic.set(row - 1, col - 1, *c) {
+                Ok(totals) => println!(
+                    "After setting row {row}, column {col} to {c}: part numbers sum to {}, gear ratios to {}",
+                    totals.part_sum, totals.ratio_sum
+                ),
+                Err(e) => println!("Couldn't set row {row}, column {col} to {c}: {e}"),
+            }
+        }
+    }
+
+    if options.show || options.render_path.is_some() {
+        let cells = CellMap::new(&symbols, &mask, &numbers, &gears);
+        if options.show {
+            print!("{}", render_ansi(&no_newlines, &cells));
+        }
+        if let Some(path) = &options.render_path {
+            write_image(path, &cells, options.scale)
+                .unwrap_or_else(|e| panic!("couldn't write {path}: {e}"));
+        }
+    }
 }
 
-fn is_symbol(c: char) -> bool {
-    !c.is_digit(10) && c != '.'
+fn parse_args(mut args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        classifier: Classifier::default(),
+        neighbourhood: Neighbourhood::Eight,
+        gears: false,
+        symbol_report: false,
+        per_symbol: false,
+        show: false,
+        render_path: None,
+        scale: 4,
+        edits: vec![],
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--symbols" => {
+                let set_str = args.next().expect("--symbols needs a set like \"*#+\" or \"^[alpha]\"");
+                options.classifier.symbols =
+                    parse_symbol_set(&set_str).unwrap_or_else(|e| panic!("bad --symbols: {e}"));
+            }
+            "--blank" => {
+                let blank = args.next().expect("--blank needs a character");
+                let mut chars = blank.chars();
+                // cells are one byte each, so the blank has to be ASCII
+                options.classifier.blank = match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii() && !c.is_ascii_control() => c,
+                    _ => panic!("--blank needs exactly one ASCII character, got {blank:?}"),
+                };
+            }
+            "--neighbourhood" => {
+                let neighbourhood = args
+                    .next()
+                    .expect("--neighbourhood needs 4, 8, chebyshev:<k> or manhattan:<k>");
+                options.neighbourhood = Neighbourhood::parse(&neighbourhood)
+                    .unwrap_or_else(|e| panic!("bad --neighbourhood: {e}"));
+            }
+            "--symbol-report" => options.symbol_report = true,
+            "--per-symbol" => options.per_symbol = true,
+            "--gears" => options.gears = true,
+            "--show" => options.show = true,
+            "--render" => {
+                options.render_path = Some(args.next().expect("--render needs a .ppm or .png path"));
+            }
+            "--set" => {
+                let edit = args.next().expect("--set needs a cell like 3,7,*");
+                options.edits.push(parse_edit(&edit).unwrap_or_else(|| {
+                    panic!("bad --set {edit}, expected <row>,<column>,<char> counting from 1")
+                }));
+            }
+            "--scale" => {
+                options.scale = args
+                    .next()
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|n| *n > 0)
+                    .expect("--scale needs a positive number of pixels per cell");
+            }
+            other => panic!(
+                "unknown argument {other}, expected --symbols <set>, --blank <char>, --neighbourhood <kind>, --symbol-report, --per-symbol, --gears, --show, --render <path>, --scale <n> or --set <row>,<column>,<char>"
+            ),
+        }
+    }
+
+    options
 }
 
-fn rotated_or_str(input: &String, offset: usize) -> String {
-    // split characters into vector
-    let input_vec: Vec<char> = input.chars().collect();
-    let mut left = input_vec.clone();
-    let mut right = input_vec.clone();
+fn parse_edit(edit: &str) -> Option<(usize, usize, char)> {
+    let mut parts = edit.splitn(3, ',');
+    let row = parts.next()?.parse::<usize>().ok().filter(|r| *r > 0)?;
+    let col = parts.next()?.parse::<usize>().ok().filter(|c| *c > 0)?;
+    let mut chars = parts.next()?.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some((row, col, c)),
+        _ => None,
+    }
+}
 
-    left.rotate_left(offset);
-    right.rotate_right(offset);
+// A gear is a * touching exactly two numbers. The numbers near a * are the ones
+// with a cell within reach of it, using the same reach part one dilates the
+// symbols by. A * only counts when it's a symbol under the classifier.
+fn find_gears(
+    diagram: &EngineDiagram,
+    numbers: &[NumberSpan],
+    classifier: &Classifier,
+    reach: &[(isize, isize)],
+) -> Vec<Gear> {
+    let stars = diagram.mask_of(|c| c == '*' && classifier.is_symbol(c));
 
-    input_vec
-        .iter()
-        .zip(left.iter().zip(right.iter()))
-        .map(|(o, (l, r))| {
-            if o == &'1' || l == &'1' || r == &'1' {
-                '1'
-            } else {
-                '0'
+    touching_symbols(&stars, numbers, reach)
+        .into_iter()
+        .filter_map(|((row, col), touching)| match touching[..] {
+            [first, second] => Some(Gear {
+                row,
+                col,
+                parts: (numbers[first].value, numbers[second].value),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+// Each symbol cell in reading order, with the indexes of the numbers within reach
+// of it. Symbols that no number reaches are left out.
+fn touching_symbols(
+    symbols: &Mask,
+    numbers: &[NumberSpan],
+    reach: &[(isize, isize)],
+) -> BTreeMap<(usize, usize), Vec<usize>> {
+    let mut touching: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+
+    for (i, number) in numbers.iter().enumerate() {
+        let near: BTreeSet<(usize, usize)> = (number.start..number.end)
+            .flat_map(|col| {
+                reach
+                    .iter()
+                    .filter_map(move |(dr, dc)| symbols.offset(number.row, col, *dr, *dc))
+            })
+            .filter(|(row, col)| symbols.get(*row, *col))
+            .collect();
+
+        near.into_iter()
+            .for_each(|symbol| touching.entry(symbol).or_default().push(i));
+    }
+
+    touching
+}
+
+fn find_numbers(diagram: &EngineDiagram) -> Vec<NumberSpan> {
+    diagram
+        .rows()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            let mut numbers = vec![];
+            let mut chars = line.char_indices().peekable();
+
+            while let Some((start, c)) = chars.next() {
+                if !c.is_ascii_digit() {
+                    continue;
+                }
+                let mut end = start + 1;
+                while chars.next_if(|(_, d)| d.is_ascii_digit()).is_some() {
+                    end += 1;
+                }
+                // validate reports numbers too big for a u64 before anything is solved
+                let value = line[start..end].parse().unwrap_or_else(|_| {
+                    panic!(
+                        "the number at row {}, column {} doesn't fit in a u64",
+                        row + 1,
+                        start + 1
+                    )
+                });
+                numbers.push(NumberSpan {
+                    value,
+                    row,
+                    start,
+                    end,
+                });
             }
+
+            numbers
         })
-        .collect::<String>()
+        .collect()
+}
+
+fn touches_mask(number: &NumberSpan, mask: &Mask) -> bool {
+    mask.any_in_span(number.row, number.start, number.end)
+}
+
+// The puzzle's own rule, anything that isn't a digit or a '.'
+fn is_symbol(c: char) -> bool {
+    Classifier::default().is_symbol(c)
 }
 
 #[cfg(test)]
@@ -77,23 +373,178 @@ mod tests {
 
     #[test]
     fn diagram_binary_fmt() {
-        let test = EngineDiagram("....#..25...1.*....@".to_string());
+        let test = EngineDiagram::new("....#..25...1.*....@");
         assert_eq!(format!("{test:b}"), "00001000000000100001".to_string())
     }
 
     #[test]
-    fn rotated_or_test() {
+    fn number_spans() {
+        let numbers = find_numbers(&EngineDiagram::new("467..114..\n...*......\n..35..633."));
+
+        assert_eq!(numbers.len(), 4);
         assert_eq!(
-            rotated_or_str(&"00001000000000100001".to_string(), 1),
-            "10011100000001110011".to_string()
+            numbers[1],
+            NumberSpan {
+                value: 114,
+                row: 0,
+                start: 5,
+                end: 8,
+            }
         );
         assert_eq!(
-            rotated_or_str(&"00001000000000100001".to_string(), 5),
-            "00001000010000100001".to_string()
+            (numbers[3].row, numbers[3].start, numbers[3].value),
+            (2, 6, 633)
+        );
+    }
+
+    #[test]
+    fn long_part_numbers() {
+        let diagram = EngineDiagram::new("12345678901.\n*...........");
+        let numbers = find_numbers(&diagram);
+
+        assert_eq!(numbers[0].value, 12345678901);
+    }
+
+    #[test]
+    fn symbols_at_row_ends() {
+        let diagram = EngineDiagram::new("..#\n1.2\n...");
+        let mask = diagram
+            .mask_of(is_symbol)
+            .dilate(&Neighbourhood::Eight.offsets());
+        let numbers = find_numbers(&diagram);
+
+        // the # is at the end of its row, so the 1 starting the next row is clear of it
+        assert!(!touches_mask(&numbers[0], &mask));
+        assert!(touches_mask(&numbers[1], &mask));
+    }
+
+    #[test]
+    fn example_part_numbers() {
+        let diagram = EngineDiagram::new(include_str!("../input_easier.txt"));
+        let mask = diagram
+            .mask_of(is_symbol)
+            .dilate(&Neighbourhood::Eight.offsets());
+        let sum: u64 = find_numbers(&diagram)
+            .iter()
+            .filter(|n| touches_mask(n, &mask))
+            .map(|n| n.value)
+            .sum();
+
+        assert_eq!(sum, 4361);
+    }
+
+    #[test]
+    fn example_gears() {
+        let diagram = EngineDiagram::new(include_str!("../input_easier.txt"));
+        let gears = find_gears(
+            &diagram,
+            &find_numbers(&diagram),
+            &Classifier::default(),
+            &Neighbourhood::Eight.offsets(),
         );
+
         assert_eq!(
-            rotated_or_str(&"000010000".to_string(), 3),
-            "010010010".to_string()
+            gears,
+            vec![
+                Gear {
+                    row: 1,
+                    col: 3,
+                    parts: (467, 35),
+                },
+                Gear {
+                    row: 8,
+                    col: 5,
+                    parts: (755, 598),
+                },
+            ]
         );
+        assert_eq!(gears.iter().map(Gear::ratio).sum::<u128>(), 467835);
+    }
+
+    #[test]
+    fn other_dialect() {
+        // underscores for blanks, and letters are labels rather than symbols
+        let diagram = EngineDiagram::new("12_a_\n___*3\n4_b__");
+        let classifier = Classifier {
+            symbols: parse_symbol_set("^[alpha]").unwrap(),
+            blank: '_',
+        };
+        let mask = diagram
+            .mask_of(|c| classifier.is_symbol(c))
+            .dilate(&Neighbourhood::Eight.offsets());
+        let parts: Vec<u64> = find_numbers(&diagram)
+            .iter()
+            .filter(|n| touches_mask(n, &mask))
+            .map(|n| n.value)
+            .collect();
+
+        assert_eq!(parts, vec![3]);
+        assert!(find_gears(
+            &diagram,
+            &find_numbers(&diagram),
+            &classifier,
+            &Neighbourhood::Eight.offsets()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn multi_byte_blank() {
+        parse_args(["--blank".to_string(), "·".to_string()].into_iter());
+    }
+
+    #[test]
+    fn symbol_args() {
+        let args = |a: &[&str]| {
+            a.iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+        let options = parse_args(args(&["--symbols", "*#", "--blank", "_", "--gears"]));
+
+        assert_eq!(options.classifier.blank, '_');
+        assert!(options.classifier.is_symbol('#'));
+        assert!(!options.classifier.is_symbol('+'));
+        assert!(options.gears && !options.symbol_report);
+        assert_eq!(parse_args(args(&[])).classifier, Classifier::default());
+        assert_eq!(parse_edit("3,7,*"), Some((3, 7, '*')));
+        assert_eq!(parse_args(args(&["--blank", " "])).classifier.blank, ' ');
+        assert_eq!(parse_edit("1,2,,"), Some((1, 2, ',')));
+        assert_eq!(parse_edit("0,2,#"), None);
+        assert_eq!(parse_edit("1,2,ab"), None);
+    }
+
+    #[test]
+    fn neighbourhoods() {
+        // the 1 only touches the * diagonally, the 2 is two steps right of it
+        let diagram = EngineDiagram::new("1...\n.*.2\n....");
+        let numbers = find_numbers(&diagram);
+        let touching = |neighbourhood: Neighbourhood| -> Vec<u64> {
+            let mask = diagram.mask_of(is_symbol).dilate(&neighbourhood.offsets());
+            numbers
+                .iter()
+                .filter(|n| touches_mask(n, &mask))
+                .map(|n| n.value)
+                .collect()
+        };
+
+        assert_eq!(touching(Neighbourhood::Four), Vec::<u64>::new());
+        assert_eq!(touching(Neighbourhood::Eight), vec![1]);
+        assert_eq!(touching(Neighbourhood::Manhattan(2)), vec![1, 2]);
+        assert_eq!(touching(Neighbourhood::Chebyshev(2)), vec![1, 2]);
+
+        let reach = Neighbourhood::Manhattan(2).offsets();
+        let gears = find_gears(&diagram, &numbers, &Classifier::default(), &reach);
+        assert_eq!(gears.len(), 1);
+        assert_eq!(gears[0].ratio(), 2);
+        assert!(find_gears(
+            &diagram,
+            &numbers,
+            &Classifier::default(),
+            &Neighbourhood::Eight.offsets()
+        )
+        .is_empty());
     }
 }
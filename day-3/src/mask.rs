@@ -0,0 +1,259 @@
+// A packed bitset over the cells of a schematic.
+//
+// Each row starts on a fresh u64, so shifting a row sideways can never carry a
+// bit into the row above or below it, and shifting up or down drops whatever
+// falls off the top or bottom edge instead of wrapping it around.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl Mask {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
+
+        Mask {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        row < self.height
+            && col < self.width
+            && self.words[row * self.words_per_row + col / 64] & (1 << (col % 64)) != 0
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: bool) {
+        assert!(
+            row < self.height && col < self.width,
+            "({row}, {col}) is outside the mask"
+        );
+        let word = &mut self.words[row * self.words_per_row + col / 64];
+
+        if value {
+            *word |= 1 << (col % 64);
+        } else {
+            *word &= !(1 << (col % 64));
+        }
+    }
+
+    // The cell (dr, dc) away from (row, col), if it's inside the mask
+    pub fn offset(&self, row: usize, col: usize, dr: isize, dc: isize) -> Option<(usize, usize)> {
+        let row = row.checked_add_signed(dr).filter(|r| *r < self.height)?;
+        let col = col.checked_add_signed(dc).filter(|c| *c < self.width)?;
+
+        Some((row, col))
+    }
+
+    // Whether any cell in columns start..end of the row is set
+    pub fn any_in_span(&self, row: usize, start: usize, end: usize) -> bool {
+        let end = end.min(self.width);
+        if row >= self.height || start >= end {
+            return false;
+        }
+
+        let row_words = &self.words[row * self.words_per_row..(row + 1) * self.words_per_row];
+        (start / 64..=(end - 1) / 64).any(|w| {
+            let low = if w == start / 64 { start % 64 } else { 0 };
+            let high = if w == (end - 1) / 64 {
+                (end - 1) % 64
+            } else {
+                63
+            };
+            let bits = (u64::MAX >> (63 - high)) & (u64::MAX << low);
+            row_words[w] & bits != 0
+        })
+    }
+
+    #[cfg(test)]
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // The mask moved dr rows down and dc columns right, dropping anything that
+    // moves off an edge
+    pub fn shifted(&self, dr: isize, dc: isize) -> Mask {
+        let mut result = Mask::new(self.width, self.height);
+        let wpr = self.words_per_row;
+
+        for row in 0..self.height {
+            let Some(source_row) = row.checked_add_signed(-dr).filter(|r| *r < self.height) else {
+                continue;
+            };
+            let source = &self.words[source_row * wpr..(source_row + 1) * wpr];
+            let target = &mut result.words[row * wpr..(row + 1) * wpr];
+            shift_row(source, target, dc);
+        }
+
+        result.clear_tail();
+        result
+    }
+
+    pub fn or_assign(&mut self, other: &Mask) {
+        assert_eq!((self.width, self.height), (other.width, other.height));
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    // Every cell within reach of a set cell, where reach is the list of (row,
+    // column) steps that count as touching
+    pub fn dilate(&self, reach: &[(isize, isize)]) -> Mask {
+        let mut result = Mask::new(self.width, self.height);
+        for (dr, dc) in reach {
+            result.or_assign(&self.shifted(*dr, *dc));
+        }
+        result
+    }
+
+    // Columns past the width in each row's last word are kept clear, so shifting
+    // right can't bring in cells that aren't there
+    fn clear_tail(&mut self) {
+        if self.width.is_multiple_of(64) {
+            return;
+        }
+        let keep = (1_u64 << (self.width % 64)) - 1;
+        for row in 0..self.height {
+            self.words[(row + 1) * self.words_per_row - 1] &= keep;
+        }
+    }
+}
+
+// Moves one row's bits dc columns to the right (left when negative)
+fn shift_row(source: &[u64], target: &mut [u64], dc: isize) {
+    let words = source.len() as isize;
+    let word_shift = dc.div_euclid(64);
+    let bit_shift = dc.rem_euclid(64) as u32;
+
+    for (i, word) in target.iter_mut().enumerate() {
+        // column c of the result comes from column c - dc of the source
+        let from = i as isize - word_shift;
+        let at = |w: isize| {
+            if (0..words).contains(&w) {
+                source[w as usize]
+            } else {
+                0
+            }
+        };
+
+        *word = if bit_shift == 0 {
+            at(from)
+        } else {
+            (at(from) << bit_shift) | (at(from - 1) >> (64 - bit_shift))
+        };
+    }
+}
+
+// Written out as 0s and 1s, one row after another with no line breaks
+impl fmt::Binary for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut binary_str = String::with_capacity(self.width * self.height);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                binary_str.push(if self.get(row, col) { '1' } else { '0' });
+            }
+        }
+
+        fmt::Display::fmt(&binary_str, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neighbourhood::Neighbourhood;
+
+    fn from_rows(rows: &[&str]) -> Mask {
+        let mut mask = Mask::new(rows[0].len(), rows.len());
+        for (r, row) in rows.iter().enumerate() {
+            for (c, cell) in row.chars().enumerate() {
+                mask.set(r, c, cell == '1');
+            }
+        }
+        mask
+    }
+
+    #[test]
+    fn get_and_set() {
+        let mut mask = Mask::new(130, 2);
+        mask.set(1, 129, true);
+        mask.set(0, 64, true);
+        mask.set(0, 64, false);
+
+        assert!(mask.get(1, 129));
+        assert!(!mask.get(0, 64));
+        assert!(!mask.get(2, 0));
+        assert_eq!(mask.count_ones(), 1);
+    }
+
+    #[test]
+    fn shifts_stay_in_their_row() {
+        let mask = from_rows(&["0001", "1000", "0000"]);
+
+        assert_eq!(mask.shifted(0, 1), from_rows(&["0000", "0100", "0000"]));
+        assert_eq!(mask.shifted(0, -1), from_rows(&["0010", "0000", "0000"]));
+        assert_eq!(mask.shifted(1, 0), from_rows(&["0000", "0001", "1000"]));
+        assert_eq!(mask.shifted(-1, 0), from_rows(&["1000", "0000", "0000"]));
+    }
+
+    #[test]
+    fn shifts_across_words() {
+        let mut mask = Mask::new(200, 1);
+        mask.set(0, 63, true);
+        mask.set(0, 130, true);
+
+        let right = mask.shifted(0, 70);
+        assert!(right.get(0, 133));
+        assert_eq!(right.count_ones(), 1);
+
+        let left = mask.shifted(0, -1);
+        assert!(left.get(0, 62) && left.get(0, 129));
+        assert_eq!(left.count_ones(), 2);
+    }
+
+    #[test]
+    fn dilate_without_wrapping() {
+        // the old rotate-based mask spilled the corner 1s onto the far side of the grid
+        let mask = from_rows(&["00001", "00000", "00000", "10000"]);
+
+        assert_eq!(
+            mask.dilate(&Neighbourhood::Eight.offsets()),
+            from_rows(&["00011", "00011", "11000", "11000"])
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let mut mask = Mask::new(150, 2);
+        mask.set(1, 100, true);
+
+        assert!(mask.any_in_span(1, 90, 101));
+        assert!(mask.any_in_span(1, 100, 101));
+        assert!(!mask.any_in_span(1, 101, 150));
+        assert!(!mask.any_in_span(0, 0, 150));
+        assert!(!mask.any_in_span(1, 20, 100));
+    }
+
+    #[test]
+    fn binary_format() {
+        assert_eq!(format!("{:b}", from_rows(&["010", "001"])), "010001");
+    }
+}
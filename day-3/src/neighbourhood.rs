@@ -0,0 +1,87 @@
+// Which cells count as touching a cell.
+//
+// The puzzle uses the 8 cells around it. Radius k versions reach further: a
+// Chebyshev radius covers the (2k + 1) square around the cell, a Manhattan radius
+// the diamond of cells at most k steps away moving only up, down, left or right.
+// So Eight is Chebyshev(1) and Four is Manhattan(1).
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighbourhood {
+    Four,
+    Eight,
+    Chebyshev(usize),
+    Manhattan(usize),
+}
+
+impl Neighbourhood {
+    pub fn parse(neighbourhood_str: &str) -> Result<Self, String> {
+        let radius = |k: &str| {
+            k.parse::<usize>()
+                .map_err(|_| format!("{k:?} isn't a radius, expected a whole number"))
+        };
+
+        match neighbourhood_str.split_once(':') {
+            None if neighbourhood_str == "4" => Ok(Neighbourhood::Four),
+            None if neighbourhood_str == "8" => Ok(Neighbourhood::Eight),
+            Some(("chebyshev", k)) => radius(k).map(Neighbourhood::Chebyshev),
+            Some(("manhattan", k)) => radius(k).map(Neighbourhood::Manhattan),
+            _ => Err(format!(
+                "unknown neighbourhood {neighbourhood_str}, expected 4, 8, chebyshev:<k> or manhattan:<k>"
+            )),
+        }
+    }
+
+    // The (row, column) steps from a cell to each cell touching it, including the
+    // cell itself
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        let (radius, within): (usize, fn(isize, isize, isize) -> bool) = match self {
+            Neighbourhood::Four => (1, manhattan),
+            Neighbourhood::Eight => (1, chebyshev),
+            Neighbourhood::Chebyshev(k) => (*k, chebyshev),
+            Neighbourhood::Manhattan(k) => (*k, manhattan),
+        };
+        let k = radius as isize;
+
+        (-k..=k)
+            .flat_map(|dr| (-k..=k).map(move |dc| (dr, dc)))
+            .filter(|(dr, dc)| within(*dr, *dc, k))
+            .collect()
+    }
+}
+
+fn chebyshev(dr: isize, dc: isize, k: isize) -> bool {
+    dr.abs().max(dc.abs()) <= k
+}
+
+fn manhattan(dr: isize, dc: isize, k: isize) -> bool {
+    dr.abs() + dc.abs() <= k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(Neighbourhood::Four.offsets().len(), 5);
+        assert_eq!(Neighbourhood::Eight.offsets().len(), 9);
+        assert_eq!(Neighbourhood::Chebyshev(2).offsets().len(), 25);
+        assert_eq!(Neighbourhood::Manhattan(2).offsets().len(), 13);
+        assert_eq!(Neighbourhood::Chebyshev(0).offsets(), vec![(0, 0)]);
+        assert_eq!(
+            Neighbourhood::Eight.offsets(),
+            Neighbourhood::Chebyshev(1).offsets()
+        );
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(Neighbourhood::parse("4"), Ok(Neighbourhood::Four));
+        assert_eq!(
+            Neighbourhood::parse("manhattan:3"),
+            Ok(Neighbourhood::Manhattan(3))
+        );
+        assert!(Neighbourhood::parse("chebyshev:-1").is_err());
+        assert!(Neighbourhood::parse("6").is_err());
+    }
+}
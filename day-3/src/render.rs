@@ -0,0 +1,257 @@
+// Draws the schematic with every cell coloured by what it turned out to be:
+//
+//   symbols         yellow
+//   gears           magenta
+//   part numbers    green
+//   other numbers   red
+//   blanks          grey
+//
+// either as ANSI coloured text or as an image, one square of pixels per cell.
+// Images are PPM or PNG depending on the file's extension.
+
+use std::fs::File;
+use std::io::{self, BufWriter, ErrorKind, Write};
+
+use crate::mask::Mask;
+use crate::{EngineDiagram, Gear, NumberSpan};
+
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cell {
+    Blank,
+    Symbol,
+    Gear,
+    Part,
+    NotPart,
+}
+
+impl Cell {
+    fn ansi(self) -> &'static str {
+        match self {
+            Cell::Blank => "\x1b[2m",
+            Cell::Symbol => "\x1b[1;33m",
+            Cell::Gear => "\x1b[1;35m",
+            Cell::Part => "\x1b[1;32m",
+            Cell::NotPart => "\x1b[1;31m",
+        }
+    }
+
+    fn rgb(self) -> [u8; 3] {
+        match self {
+            Cell::Blank => [40, 40, 40],
+            Cell::Symbol => [240, 200, 40],
+            Cell::Gear => [220, 60, 220],
+            Cell::Part => [60, 200, 80],
+            Cell::NotPart => [220, 50, 50],
+        }
+    }
+}
+
+pub struct CellMap {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl CellMap {
+    // Symbols come from the symbol mask, and numbers are parts when they touch the
+    // dilated mask, exactly as the answers were worked out
+    pub fn new(symbols: &Mask, dilated: &Mask, numbers: &[NumberSpan], gears: &[Gear]) -> Self {
+        let (width, height) = (symbols.width(), symbols.height());
+        let mut cells = vec![Cell::Blank; width * height];
+
+        for row in 0..height {
+            for col in 0..width {
+                if symbols.get(row, col) {
+                    cells[row * width + col] = Cell::Symbol;
+                }
+            }
+        }
+        for gear in gears {
+            cells[gear.row * width + gear.col] = Cell::Gear;
+        }
+        for number in numbers {
+            let kind = if dilated.any_in_span(number.row, number.start, number.end) {
+                Cell::Part
+            } else {
+                Cell::NotPart
+            };
+            cells[number.row * width + number.start..number.row * width + number.end].fill(kind);
+        }
+
+        CellMap {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cell {
+        self.cells[row * self.width + col]
+    }
+
+    // RGB bytes for the whole image, each cell drawn as a scale x scale square
+    fn pixels(&self, scale: usize) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(self.width * self.height * scale * scale * 3);
+
+        for row in 0..self.height * scale {
+            for col in 0..self.width * scale {
+                pixels.extend(self.cell(row / scale, col / scale).rgb());
+            }
+        }
+
+        pixels
+    }
+}
+
+pub fn render_ansi(diagram: &EngineDiagram, cells: &CellMap) -> String {
+    let mut rendered = String::new();
+
+    for (row, line) in diagram.rows().enumerate() {
+        let mut current = None;
+        for (col, c) in line.chars().enumerate() {
+            let cell = cells.cell(row, col);
+            // only switch colour when the kind of cell changes
+            if current != Some(cell) {
+                rendered.push_str(RESET);
+                rendered.push_str(cell.ansi());
+                current = Some(cell);
+            }
+            rendered.push(c);
+        }
+        rendered.push_str(RESET);
+        rendered.push('\n');
+    }
+
+    rendered
+}
+
+pub fn write_ppm(out: &mut impl Write, cells: &CellMap, scale: usize) -> io::Result<()> {
+    write!(
+        out,
+        "P6\n{} {}\n255\n",
+        cells.width * scale,
+        cells.height * scale
+    )?;
+    out.write_all(&cells.pixels(scale))
+}
+
+pub fn write_png(out: impl Write, cells: &CellMap, scale: usize) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(
+        out,
+        (cells.width * scale) as u32,
+        (cells.height * scale) as u32,
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&cells.pixels(scale))?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+pub fn write_image(path: &str, cells: &CellMap, scale: usize) -> io::Result<()> {
+    // the extension is checked first so a bad path doesn't leave an empty file behind
+    let png = match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("ppm") => false,
+        Some("png") => true,
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("can't tell what kind of image {path} is, expected a .ppm or .png file"),
+            ))
+        }
+    };
+
+    let mut out = BufWriter::new(File::create(path)?);
+    if png {
+        write_png(&mut out, cells, scale)?;
+    } else {
+        write_ppm(&mut out, cells, scale)?;
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neighbourhood::Neighbourhood;
+    use crate::symbols::Classifier;
+    use crate::{find_gears, find_numbers, is_symbol};
+
+    fn example() -> (EngineDiagram, CellMap) {
+        let diagram = EngineDiagram::new("1*2.\n...3\n#..4");
+        let reach = Neighbourhood::Eight.offsets();
+        let symbols = diagram.mask_of(is_symbol);
+        let numbers = find_numbers(&diagram);
+        let gears = find_gears(&diagram, &numbers, &Classifier::default(), &reach);
+        let cells = CellMap::new(&symbols, &symbols.dilate(&reach), &numbers, &gears);
+
+        (diagram, cells)
+    }
+
+    #[test]
+    fn cell_kinds() {
+        let (_, cells) = example();
+
+        assert_eq!(cells.cell(0, 0), Cell::Part);
+        assert_eq!(cells.cell(0, 1), Cell::Gear);
+        assert_eq!(cells.cell(1, 3), Cell::NotPart);
+        assert_eq!(cells.cell(2, 0), Cell::Symbol);
+        assert_eq!(cells.cell(2, 3), Cell::NotPart);
+        assert_eq!(cells.cell(1, 0), Cell::Blank);
+    }
+
+    #[test]
+    fn ansi_keeps_the_text() {
+        let (diagram, cells) = example();
+        let rendered = render_ansi(&diagram, &cells);
+        let plain: String = rendered
+            .split('\x1b')
+            .fold(String::new(), |mut plain, piece| {
+                plain.push_str(piece.split_once('m').map_or(piece, |(_, text)| text));
+                plain
+            });
+
+        assert_eq!(plain, "1*2.\n...3\n#..4\n");
+        assert!(rendered.contains(&format!("{}*", Cell::Gear.ansi())));
+    }
+
+    #[test]
+    fn ppm_image() {
+        let (_, cells) = example();
+        let mut ppm = vec![];
+        write_ppm(&mut ppm, &cells, 2).unwrap();
+
+        let header = b"P6\n8 6\n255\n";
+        assert!(ppm.starts_with(header));
+        assert_eq!(ppm.len(), header.len() + 8 * 6 * 3);
+        // the second pixel of the top row is still the 1, the third is the gear
+        assert_eq!(ppm[header.len() + 3..header.len() + 6], Cell::Part.rgb());
+        assert_eq!(ppm[header.len() + 6..header.len() + 9], Cell::Gear.rgb());
+    }
+
+    #[test]
+    fn png_image() {
+        let (_, cells) = example();
+        let mut png = vec![];
+        write_png(&mut png, &cells, 1).unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[test]
+    fn unknown_image_kind() {
+        let (_, cells) = example();
+        let path = std::env::temp_dir().join("day-3-render-test.jpg");
+        let path = path.to_str().unwrap();
+        let error = write_image(path, &cells, 1).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(!std::path::Path::new(path).exists());
+    }
+}
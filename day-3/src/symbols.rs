@@ -0,0 +1,185 @@
+// Which characters in a schematic count as symbols.
+//
+// Digits are always part of a number and the blank character is always filler,
+// so neither can be a symbol. Everything else is decided by a symbol set, written
+// as a list of characters and classes:
+//
+//   "*#+"            only those characters, which must be ASCII
+//   "[punct]"        any ASCII punctuation, and likewise [alpha], [space], [any]
+//   "^[alpha]_"      everything except letters and underscores
+//
+// The default set is "^" (everything except nothing) with '.' as the blank, which
+// is the puzzle's rule: anything that isn't a digit or a '.' is a symbol.
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharClass {
+    Char(char),
+    Punctuation,
+    Alphabetic,
+    Whitespace,
+    Any,
+}
+
+impl CharClass {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            CharClass::Char(class_char) => *class_char == c,
+            CharClass::Punctuation => c.is_ascii_punctuation(),
+            CharClass::Alphabetic => c.is_alphabetic(),
+            CharClass::Whitespace => c.is_whitespace(),
+            CharClass::Any => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolSet {
+    Only(Vec<CharClass>),
+    Except(Vec<CharClass>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classifier {
+    pub symbols: SymbolSet,
+    pub blank: char,
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        Classifier {
+            symbols: SymbolSet::Except(vec![]),
+            blank: '.',
+        }
+    }
+}
+
+impl Classifier {
+    pub fn is_symbol(&self, c: char) -> bool {
+        if c.is_ascii_digit() || c == self.blank {
+            return false;
+        }
+
+        match &self.symbols {
+            SymbolSet::Only(classes) => classes.iter().any(|class| class.contains(c)),
+            SymbolSet::Except(classes) => !classes.iter().any(|class| class.contains(c)),
+        }
+    }
+}
+
+pub fn parse_symbol_set(set_str: &str) -> Result<SymbolSet, String> {
+    let (except, mut rest) = match set_str.strip_prefix('^') {
+        Some(rest) => (true, rest),
+        None => (false, set_str),
+    };
+    let mut classes = vec![];
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let (name, after) = after
+                .split_once(']')
+                .ok_or_else(|| format!("unclosed [ in symbol set {set_str:?}"))?;
+            classes.push(match name {
+                "punct" => CharClass::Punctuation,
+                "alpha" => CharClass::Alphabetic,
+                "space" => CharClass::Whitespace,
+                "any" => CharClass::Any,
+                other => {
+                    return Err(format!(
+                        "unknown class [{other}], expected [punct], [alpha], [space] or [any]"
+                    ))
+                }
+            });
+            rest = after;
+        } else if !c.is_ascii() {
+            // cells are one byte each, so anything wider can never be a cell
+            return Err(format!("{c:?} isn't an ASCII character"));
+        } else {
+            classes.push(CharClass::Char(c));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    Ok(if except {
+        SymbolSet::Except(classes)
+    } else {
+        SymbolSet::Only(classes)
+    })
+}
+
+// Each distinct symbol in the input and how many times it appears
+pub fn symbol_counts(input: &str, classifier: &Classifier) -> BTreeMap<char, usize> {
+    let mut counts = BTreeMap::new();
+
+    input
+        .chars()
+        .filter(|c| *c != '\n' && classifier.is_symbol(*c))
+        .for_each(|c| *counts.entry(c).or_insert(0) += 1);
+
+    counts
+}
+
+pub fn symbol_report(counts: &BTreeMap<char, usize>) -> String {
+    let mut report = format!("{} distinct symbols\n", counts.len());
+
+    for (symbol, count) in counts {
+        report.push_str(&format!("  {symbol} appears {count} times\n"));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classifier(set_str: &str, blank: char) -> Classifier {
+        Classifier {
+            symbols: parse_symbol_set(set_str).unwrap(),
+            blank,
+        }
+    }
+
+    #[test]
+    fn default_rule() {
+        let default = Classifier::default();
+
+        assert!(default.is_symbol('#'));
+        assert!(default.is_symbol('a'));
+        assert!(!default.is_symbol('.'));
+        assert!(!default.is_symbol('7'));
+        assert_eq!(default, classifier("^", '.'));
+    }
+
+    #[test]
+    fn explicit_and_classes() {
+        let only = classifier("*#", '.');
+        assert!(only.is_symbol('*') && only.is_symbol('#'));
+        assert!(!only.is_symbol('+'));
+
+        let punctuation = classifier("[punct]", '_');
+        assert!(punctuation.is_symbol('.'));
+        assert!(!punctuation.is_symbol('_'));
+        assert!(!punctuation.is_symbol('a'));
+
+        let no_letters = classifier("^[alpha]", '.');
+        assert!(no_letters.is_symbol('%'));
+        assert!(!no_letters.is_symbol('a'));
+    }
+
+    #[test]
+    fn bad_sets() {
+        assert!(parse_symbol_set("[punct").is_err());
+        assert!(parse_symbol_set("[digits]").is_err());
+        assert!(parse_symbol_set("*·").is_err());
+    }
+
+    #[test]
+    fn counts() {
+        let counts = symbol_counts("*.#\n.*a\n", &Classifier::default());
+
+        assert_eq!(counts, BTreeMap::from([('#', 1), ('*', 2), ('a', 1)]));
+        assert!(symbol_report(&counts).starts_with("3 distinct symbols\n"));
+    }
+}
@@ -0,0 +1,280 @@
+// Checks a schematic is well formed before it's solved. EngineDiagram takes the
+// width from the first row and joins every row together, so anything wrong with
+// the layout would otherwise quietly shift the cells after it.
+//
+// Lines and columns are counted from 1, the way an editor shows them.
+
+use std::fmt;
+
+use crate::symbols::Classifier;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    Empty,
+    // a row that isn't as wide as the first one
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    // a character that isn't a digit, the blank or a symbol, so it would be read
+    // as filler without saying so. Cells are also one printable ASCII character
+    // each, so spaces (unless they're the blank), control characters and
+    // non-ASCII characters are always unexpected.
+    UnexpectedChar {
+        line: usize,
+        col: usize,
+        c: char,
+    },
+    // a run of digits too big for a u64, which can't be a part number
+    NumberTooBig {
+        line: usize,
+        col: usize,
+        digits: usize,
+    },
+    // a row that ends in a digit followed by a row starting with one. They're
+    // read as two numbers, but used to be read as one running across both rows,
+    // so this is only a warning. The column is where the first number starts.
+    SplitNumber {
+        line: usize,
+        col: usize,
+    },
+}
+
+impl Problem {
+    // whether the schematic can still be solved with this problem in it
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Problem::SplitNumber { .. })
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Empty => write!(f, "the schematic is empty"),
+            Problem::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: the row is {found} wide but the first row is {expected}"
+            ),
+            Problem::UnexpectedChar { line, col, c } => {
+                write!(f, "line {line}, column {col}: unexpected character {c:?}")
+            }
+            Problem::NumberTooBig { line, col, digits } => write!(
+                f,
+                "line {line}, column {col}: the {digits} digit number is too big for a u64"
+            ),
+            Problem::SplitNumber { line, col } => write!(
+                f,
+                "line {line}, column {col}: the number at the end of the row is followed by one at the start of line {}",
+                line + 1
+            ),
+        }
+    }
+}
+
+pub fn validate(input: &str, classifier: &Classifier) -> Vec<Problem> {
+    let mut rows: Vec<&str> = input.lines().collect();
+    // blank lines at the end of the file aren't rows
+    while rows.last().is_some_and(|row| row.is_empty()) {
+        rows.pop();
+    }
+    if rows.is_empty() {
+        return vec![Problem::Empty];
+    }
+
+    let expected = rows[0].chars().count();
+    let mut problems = vec![];
+
+    for (i, row) in rows.iter().enumerate() {
+        let line = i + 1;
+        let found = row.chars().count();
+        if found != expected {
+            problems.push(Problem::RaggedRow {
+                line,
+                expected,
+                found,
+            });
+        }
+
+        row.chars()
+            .enumerate()
+            .filter(|(_, c)| !is_cell(*c, classifier))
+            .for_each(|(col, c)| {
+                problems.push(Problem::UnexpectedChar {
+                    line,
+                    col: col + 1,
+                    c,
+                })
+            });
+
+        let mut chars = row.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if !c.is_ascii_digit() {
+                continue;
+            }
+            let mut end = start + 1;
+            while chars.next_if(|(_, d)| d.is_ascii_digit()).is_some() {
+                end += 1;
+            }
+            if row[start..end].parse::<u64>().is_err() {
+                problems.push(Problem::NumberTooBig {
+                    line,
+                    col: row[..start].chars().count() + 1,
+                    digits: end - start,
+                });
+            }
+        }
+
+        let ends_in_digit = row.chars().next_back().is_some_and(|c| c.is_ascii_digit());
+        let next_starts_with_digit = rows
+            .get(i + 1)
+            .and_then(|next| next.chars().next())
+            .is_some_and(|c| c.is_ascii_digit());
+        if ends_in_digit && next_starts_with_digit {
+            let digits = row.chars().rev().take_while(|c| c.is_ascii_digit()).count();
+            problems.push(Problem::SplitNumber {
+                line,
+                col: found - digits + 1,
+            });
+        }
+    }
+
+    problems
+}
+
+pub fn is_cell(c: char, classifier: &Classifier) -> bool {
+    (c.is_ascii_graphic() || c == classifier.blank)
+        && (c.is_ascii_digit() || c == classifier.blank || classifier.is_symbol(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::parse_symbol_set;
+
+    #[test]
+    fn well_formed() {
+        let input = include_str!("../input_easier.txt");
+
+        assert_eq!(validate(input, &Classifier::default()), vec![]);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(validate("", &Classifier::default()), vec![Problem::Empty]);
+        assert_eq!(
+            validate("\n\n", &Classifier::default()),
+            vec![Problem::Empty]
+        );
+    }
+
+    #[test]
+    fn layout_problems() {
+        let problems = validate("..12\n3.\n\n....\n\n\n", &Classifier::default());
+
+        // the blank line in the middle is a row, the ones at the end aren't
+        assert_eq!(
+            problems,
+            vec![
+                Problem::SplitNumber { line: 1, col: 3 },
+                Problem::RaggedRow {
+                    line: 2,
+                    expected: 4,
+                    found: 2,
+                },
+                Problem::RaggedRow {
+                    line: 3,
+                    expected: 4,
+                    found: 0,
+                },
+            ]
+        );
+        assert!(problems[0].is_warning() && !problems[1].is_warning());
+        assert_eq!(
+            problems[0].to_string(),
+            "line 1, column 3: the number at the end of the row is followed by one at the start of line 2"
+        );
+    }
+
+    #[test]
+    fn trailing_newlines() {
+        assert_eq!(validate("1.*\n..2\n\n", &Classifier::default()), vec![]);
+    }
+
+    #[test]
+    fn unexpected_characters() {
+        let problems = validate("1.*+\n. \té", &Classifier::default());
+
+        assert_eq!(
+            problems,
+            vec![
+                Problem::UnexpectedChar {
+                    line: 2,
+                    col: 2,
+                    c: ' ',
+                },
+                Problem::UnexpectedChar {
+                    line: 2,
+                    col: 3,
+                    c: '\t',
+                },
+                Problem::UnexpectedChar {
+                    line: 2,
+                    col: 4,
+                    c: 'é',
+                },
+            ]
+        );
+        assert_eq!(
+            problems[0].to_string(),
+            "line 2, column 2: unexpected character ' '"
+        );
+
+        // a space is fine once it's the blank
+        let spaces = Classifier {
+            symbols: parse_symbol_set("^").unwrap(),
+            blank: ' ',
+        };
+        assert_eq!(validate("1 *\n  2", &spaces), vec![]);
+
+        // with only some symbols, anything else is unexpected rather than filler
+        let some = Classifier {
+            symbols: parse_symbol_set("*#").unwrap(),
+            blank: '.',
+        };
+        assert_eq!(
+            validate("1.*a\n#..2", &some),
+            vec![Problem::UnexpectedChar {
+                line: 1,
+                col: 4,
+                c: 'a',
+            }]
+        );
+    }
+
+    #[test]
+    fn huge_numbers() {
+        let problems = validate(
+            "99999999999999999999.\n*....................",
+            &Classifier::default(),
+        );
+
+        assert_eq!(
+            problems,
+            vec![Problem::NumberTooBig {
+                line: 1,
+                col: 1,
+                digits: 20,
+            }]
+        );
+        // ten digits used to be too many, but fit fine in a u64
+        assert_eq!(
+            validate("12345678901.\n*...........", &Classifier::default()),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+// How many copies of each card end up being held, and optionally where they all
+// came from. A card with n copies wins n copies of each card it wins, so the trace
+// has one edge per (winner, won) pair weighted by how many copies went along it.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::Card;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Win {
+    pub from: u32,
+    pub to: u32,
+    pub copies: u32,
+}
+
+// Card ID -> copies held, including the original. Copies are won by ID, so the
+// cards can be listed in any order. Cards are handled from the lowest ID up, since
+// a card only wins copies of later ones.
+pub fn copy_counts(cards: &[Card], mut trace: Option<&mut Vec<Win>>) -> BTreeMap<u32, u32> {
+    let mut by_id: Vec<&Card> = cards.iter().collect();
+    by_id.sort_by_key(|c| c.id);
+    let mut card_counts: Vec<u32> = vec![1; by_id.len()];
+
+    for (i, c) in by_id.iter().enumerate() {
+        let last_won = c.id.saturating_add(c.matches() as u32);
+        let won = (i + 1..by_id.len()).take_while(|n| by_id[*n].id <= last_won);
+
+        for n in won {
+            card_counts[n] += card_counts[i];
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(Win {
+                    from: c.id,
+                    to: by_id[n].id,
+                    copies: card_counts[i],
+                });
+            }
+        }
+    }
+
+    by_id.iter().map(|c| c.id).zip(card_counts).collect()
+}
+
+// The cascade as a Graphviz graph, with each card labelled by how many copies of
+// it were held and each edge by how many copies it carried
+pub fn to_dot(counts: &BTreeMap<u32, u32>, trace: &[Win]) -> String {
+    let mut dot = String::from("digraph cascade {\n");
+
+    for (id, copies) in counts {
+        writeln!(dot, "    {id} [label=\"Card {id}\\n{copies} held\"];").unwrap();
+    }
+    for win in trace {
+        writeln!(
+            dot,
+            "    {} -> {} [label=\"{}\", weight={}];",
+            win.from, win.to, win.copies, win.copies
+        )
+        .unwrap();
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_card;
+
+    fn test_cards() -> Vec<Card> {
+        include_str!("../test_input.txt")
+            .lines()
+            .map(|l| parse_card(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn example_counts() {
+        let counts = copy_counts(&test_cards(), None);
+
+        assert_eq!(
+            counts,
+            BTreeMap::from([(1, 1), (2, 2), (3, 4), (4, 8), (5, 14), (6, 1)])
+        );
+    }
+
+    #[test]
+    fn example_trace() {
+        let mut trace = vec![];
+        let counts = copy_counts(&test_cards(), Some(&mut trace));
+
+        // card 1 wins 2, 3, 4 and 5, card 2 wins 3 and 4, card 3 wins 4 and 5, card 4 wins 5
+        assert_eq!(trace.len(), 9);
+        assert_eq!(
+            trace[5],
+            Win {
+                from: 2,
+                to: 4,
+                copies: 2,
+            }
+        );
+        // every copy beyond the original came in along an edge
+        for (id, copies) in &counts {
+            let won: u32 = trace.iter().filter(|w| w.to == *id).map(|w| w.copies).sum();
+            assert_eq!(won + 1, *copies);
+        }
+
+        let dot = to_dot(&counts, &trace);
+        assert!(dot.starts_with("digraph cascade {\n    1 [label=\"Card 1\\n1 held\"];\n"));
+        assert!(dot.contains("    3 -> 5 [label=\"4\", weight=4];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,119 @@
+// Checks the card IDs make up a proper table: every ID from 1 up to the highest
+// appears exactly once. Cards listed out of order are still a proper table, since
+// copies are won by ID, so those are only worth a note.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::Card;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdProblem {
+    Duplicate(u32),
+    // every ID from first to last, inclusive
+    Missing { first: u32, last: u32 },
+    OutOfOrder { id: u32, previous: u32 },
+}
+
+impl IdProblem {
+    // whether the answers can still be trusted with this problem in the input
+    pub fn is_harmless(&self) -> bool {
+        matches!(self, IdProblem::OutOfOrder { .. })
+    }
+}
+
+impl fmt::Display for IdProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdProblem::Duplicate(id) => write!(f, "card {id} appears more than once"),
+            IdProblem::Missing { first, last } if first == last => {
+                write!(f, "card {first} is missing")
+            }
+            IdProblem::Missing { first, last } => write!(f, "cards {first} to {last} are missing"),
+            IdProblem::OutOfOrder { id, previous } => {
+                write!(f, "card {id} comes after card {previous}")
+            }
+        }
+    }
+}
+
+pub fn check_ids(cards: &[Card]) -> Vec<IdProblem> {
+    let mut problems = vec![];
+    let mut seen: BTreeMap<u32, usize> = BTreeMap::new();
+
+    for (i, card) in cards.iter().enumerate() {
+        if i > 0 && card.id < cards[i - 1].id {
+            problems.push(IdProblem::OutOfOrder {
+                id: card.id,
+                previous: cards[i - 1].id,
+            });
+        }
+        *seen.entry(card.id).or_insert(0) += 1;
+    }
+
+    problems.extend(
+        seen.iter()
+            .filter(|(_, count)| **count > 1)
+            .map(|(id, _)| IdProblem::Duplicate(*id)),
+    );
+    // gaps are reported as ranges, so one huge ID doesn't list billions of cards
+    let mut previous = 0;
+    for id in seen.keys() {
+        if *id > previous + 1 {
+            problems.push(IdProblem::Missing {
+                first: previous + 1,
+                last: id - 1,
+            });
+        }
+        previous = *id;
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(ids: &[u32]) -> Vec<Card> {
+        ids.iter().map(|id| Card::new(*id, &[], &[])).collect()
+    }
+
+    #[test]
+    fn proper_table() {
+        assert_eq!(check_ids(&cards(&[1, 2, 3])), vec![]);
+    }
+
+    #[test]
+    fn bad_tables() {
+        let problems = check_ids(&cards(&[2, 1, 4, 4]));
+
+        assert_eq!(
+            problems,
+            vec![
+                IdProblem::OutOfOrder { id: 1, previous: 2 },
+                IdProblem::Duplicate(4),
+                IdProblem::Missing { first: 3, last: 3 },
+            ]
+        );
+        assert!(problems[0].is_harmless() && !problems[1].is_harmless());
+        assert_eq!(problems[2].to_string(), "card 3 is missing");
+    }
+
+    #[test]
+    fn missing_ranges() {
+        let problems = check_ids(&cards(&[2, 3, 4_000_000_000]));
+
+        assert_eq!(
+            problems,
+            vec![
+                IdProblem::Missing { first: 1, last: 1 },
+                IdProblem::Missing {
+                    first: 4,
+                    last: 3_999_999_999,
+                },
+            ]
+        );
+        assert_eq!(problems[1].to_string(), "cards 4 to 3999999999 are missing");
+    }
+}
@@ -1,82 +1,143 @@
+use std::collections::{BTreeMap, HashSet};
+
+use cascade::{copy_counts, to_dot};
+use ids::check_ids;
+use number_set::NumberSet;
+
+mod cascade;
+mod ids;
+mod number_set;
+
 #[derive(Debug, Clone)]
 struct Card {
-    winning: Vec<i32>,
-    held: Vec<i32>,
+    id: u32,
+    // how many of the winning numbers are held, worked out once when the card is
+    // made since it's all either problem needs
+    matches: usize,
 }
 
 impl Card {
+    fn new(id: u32, winning: &[i32], held: &[i32]) -> Self {
+        let held_set = NumberSet::new(held);
+
+        Card {
+            id,
+            matches: winning.iter().filter(|w| held_set.contains(**w)).count(),
+        }
+    }
+
     fn get_score(&self) -> i32 {
-        let held_winning = self.held_winning_nums();
-
-        if held_winning.is_empty() {
-            0
-        } else {
-            match held_winning.len().try_into().unwrap() {
-                1 => 1,
-                len => 2_i32.pow(len - 1),
-            }
+        match self.matches {
+            0 => 0,
+            matches => 2_i32.pow(matches as u32 - 1),
         }
     }
 
-    fn held_winning_nums(&self) -> Vec<i32> {
-        self.winning
-            .iter()
-            .filter(|w| self.held.iter().any(|h| h == *w))
-            .copied()
-            .collect()
+    fn matches(&self) -> usize {
+        self.matches
     }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
     let input = include_str!("../input.txt");
 
-    let parsed_cards: Vec<Card> = input.lines().map(parse_card).collect();
+    let parsed_cards: Vec<Card> = input
+        .lines()
+        .map(parse_card)
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    let (notes, problems): (Vec<_>, Vec<_>) = check_ids(&parsed_cards)
+        .into_iter()
+        .partition(|p| p.is_harmless());
+    notes.iter().for_each(|note| eprintln!("Note: {note}"));
+    if !problems.is_empty() {
+        let problems: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        panic!(
+            "the cards don't make a proper table:\n{}",
+            problems.join("\n")
+        );
+    }
 
     // Problem 1
     let total_points: i32 = parsed_cards.iter().map(|c| c.get_score()).sum();
     println!("Problem 1:\nThe total points won is {total_points}");
 
+    // `--counts` lists the copies of every card, `--trace` which card won copies of
+    // which, and `--dot <path>` writes the cascade out as a Graphviz graph
+    let dot_path = args.iter().position(|a| a == "--dot").map(|i| {
+        args.get(i + 1)
+            .expect("--dot needs a path to write the graph to")
+    });
+    let wants_trace = dot_path.is_some() || args.iter().any(|a| a == "--trace");
+    let mut trace = vec![];
+    let counts = copy_counts(&parsed_cards, wants_trace.then_some(&mut trace));
+
     // Problem 2
-    let total_cards: u32 = get_total_cards_won(&parsed_cards);
+    let total_cards: u32 = get_total_cards_won(&counts);
     println!("\nProblem 2:\nThe total number of scratch cards won is {total_cards}");
 
+    if args.iter().any(|a| a == "--counts") {
+        println!();
+        counts
+            .iter()
+            .for_each(|(id, copies)| println!("Card {id}: {copies} held"));
+    }
+    if args.iter().any(|a| a == "--trace") {
+        println!();
+        trace.iter().for_each(|win| {
+            println!(
+                "Card {} won {} copies of card {}",
+                win.from, win.copies, win.to
+            )
+        });
+    }
+    if let Some(path) = dot_path {
+        std::fs::write(path, to_dot(&counts, &trace))
+            .unwrap_or_else(|e| panic!("couldn't write {path}: {e}"));
+    }
 }
 
-fn get_total_cards_won(cards: &Vec<Card>) -> u32 {
-    let mut card_counts: Vec<u32> = vec![1; cards.len()];
-
-    cards.iter().enumerate().for_each(|(i, c)| {
-        if i == card_counts.len() {
-            return;
-        }
-
-        let held_winning_count: usize = c.held_winning_nums().len();
-
-        let start = (i + 1).min(card_counts.len());
-        let end = (i + 1 + held_winning_count).min(card_counts.len());
+// The cards held once the cascade is done, from copy_counts' copies of each card
+fn get_total_cards_won(counts: &BTreeMap<u32, u32>) -> u32 {
+    counts.values().sum()
+}
 
-        for n in start..end {
-            card_counts[n] += card_counts[i];
+fn parse_card(input: &str) -> Result<Card, String> {
+    let (name, numbers) = input
+        .split_once(':')
+        .ok_or_else(|| format!("{input:?} doesn't start with \"Card <id>:\""))?;
+    let id = name
+        .strip_prefix("Card")
+        .and_then(|id| id.trim().parse::<u32>().ok())
+        .ok_or_else(|| format!("{name:?} isn't a card ID like \"Card 12\""))?;
+    let (winning, held) = numbers
+        .split_once('|')
+        .ok_or_else(|| format!("card {id} has no | between its lists"))?;
+
+    let winning: Vec<i32> = parse_num_list(winning).map_err(|e| format!("card {id}: {e}"))?;
+    let held: Vec<i32> = parse_num_list(held).map_err(|e| format!("card {id}: {e}"))?;
+    for (list, name) in [(&winning, "winning"), (&held, "held")] {
+        if let Some(number) = first_repeat(list) {
+            return Err(format!(
+                "card {id} has {number} twice in its {name} numbers"
+            ));
         }
-    });
+    }
 
-    card_counts.iter().sum()
+    Ok(Card::new(id, &winning, &held))
 }
 
-fn parse_card(input: &str) -> Card {
-    let split_input = input.split(':');
-    let mut num_lists = split_input.last().unwrap().split('|');
-
-    let winning: Vec<i32> = parse_num_list(num_lists.next().unwrap());
-    let held: Vec<i32> = parse_num_list(num_lists.last().unwrap());
-
-    Card { winning, held }
+fn first_repeat(numbers: &[i32]) -> Option<i32> {
+    let mut seen = HashSet::new();
+    numbers.iter().copied().find(|n| !seen.insert(*n))
 }
 
-fn parse_num_list(num_list: &str) -> Vec<i32> {
+fn parse_num_list(num_list: &str) -> Result<Vec<i32>, String> {
     num_list
         .split_whitespace()
-        .map(|s| s.parse().unwrap())
+        .map(|s| s.parse().map_err(|_| format!("{s:?} isn't a number")))
         .collect()
 }
 
@@ -91,25 +152,13 @@ mod tests {
 
     #[test]
     fn calculate_score() {
-        let four_matches = Card {
-            winning: vec![41, 48, 83, 86, 17],
-            held: vec![83, 86, 6, 31, 17, 9, 48, 53],
-        };
-
-        let two_matches = Card {
-            winning: vec![13, 32, 20, 16, 61],
-            held: vec![61, 30, 68, 82, 17, 32, 24, 19],
-        };
-
-        let one_match = Card {
-            winning: vec![41, 92, 73, 84, 69],
-            held: vec![59, 84, 76, 51, 58, 5, 54, 83],
-        };
-
-        let no_matches = Card {
-            winning: vec![31, 18, 13, 56, 72],
-            held: vec![74, 77, 10, 23, 35, 67, 36, 11],
-        };
+        let four_matches = Card::new(0, &[41, 48, 83, 86, 17], &[83, 86, 6, 31, 17, 9, 48, 53]);
+
+        let two_matches = Card::new(0, &[13, 32, 20, 16, 61], &[61, 30, 68, 82, 17, 32, 24, 19]);
+
+        let one_match = Card::new(0, &[41, 92, 73, 84, 69], &[59, 84, 76, 51, 58, 5, 54, 83]);
+
+        let no_matches = Card::new(0, &[31, 18, 13, 56, 72], &[74, 77, 10, 23, 35, 67, 36, 11]);
 
         assert_eq!(four_matches.get_score(), 8);
         assert_eq!(two_matches.get_score(), 2);
@@ -120,12 +169,13 @@ mod tests {
     #[test]
     fn test_winning_counts() {
         let test_input: &str = include_str!("../test_input.txt");
-        let test_parsed_cards: Vec<Card> = test_input.lines().map(parse_card).collect();
+        let test_parsed_cards: Vec<Card> =
+            test_input.lines().map(|l| parse_card(l).unwrap()).collect();
 
         assert_eq!(
             test_parsed_cards
                 .iter()
-                .map(|c| c.held_winning_nums().len())
+                .map(Card::matches)
                 .collect::<Vec<usize>>(),
             vec![4, 2, 2, 1, 0, 0]
         );
@@ -134,8 +184,41 @@ mod tests {
     #[test]
     fn test_total_cards() {
         let test_input: &str = include_str!("../test_input.txt");
-        let test_parsed_cards: Vec<Card> = test_input.lines().map(parse_card).collect();
+        let test_parsed_cards: Vec<Card> =
+            test_input.lines().map(|l| parse_card(l).unwrap()).collect();
 
-        assert_eq!(get_total_cards_won(&test_parsed_cards), 30);
+        assert_eq!(
+            get_total_cards_won(&copy_counts(&test_parsed_cards, None)),
+            30
+        );
+    }
+
+    #[test]
+    fn shuffled_cards() {
+        let test_input: &str = include_str!("../test_input.txt");
+        let mut cards: Vec<Card> = test_input.lines().map(|l| parse_card(l).unwrap()).collect();
+        cards.reverse();
+        cards.swap(1, 4);
+
+        assert_eq!(cards[0].id, 6);
+        assert_eq!(get_total_cards_won(&copy_counts(&cards, None)), 30);
+    }
+
+    #[test]
+    fn card_parsing() {
+        let card = parse_card("Card  12: 1 2 3 | 3 2 9").unwrap();
+
+        assert_eq!((card.id, card.matches()), (12, 2));
+        assert_eq!(
+            parse_card("Card 3: 1 2 1 | 4").unwrap_err(),
+            "card 3 has 1 twice in its winning numbers"
+        );
+        assert!(parse_card("Card 4: 1 2 | 5 5").is_err());
+        assert!(parse_card("Cart 4: 1 | 2").is_err());
+        assert_eq!(
+            parse_card("Card 3: 1 x | 2").unwrap_err(),
+            "card 3: \"x\" isn't a number"
+        );
+        assert!(parse_card("Card 4: 1 2").is_err());
     }
 }
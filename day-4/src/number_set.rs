@@ -0,0 +1,52 @@
+// A set of card numbers built once when the card is parsed. Puzzle numbers are
+// all below 100, so they fit in the bits of a single u128; a list holding anything
+// outside 0..128 falls back to a HashSet.
+
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberSet {
+    Bits(u128),
+    Hashed(HashSet<i32>),
+}
+
+impl NumberSet {
+    pub fn new(numbers: &[i32]) -> Self {
+        if numbers.iter().all(|n| (0..128).contains(n)) {
+            NumberSet::Bits(numbers.iter().fold(0, |bits, n| bits | 1 << n))
+        } else {
+            NumberSet::Hashed(numbers.iter().copied().collect())
+        }
+    }
+
+    pub fn contains(&self, number: i32) -> bool {
+        match self {
+            NumberSet::Bits(bits) => (0..128).contains(&number) && bits & (1 << number) != 0,
+            NumberSet::Hashed(set) => set.contains(&number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_numbers_use_bits() {
+        let set = NumberSet::new(&[0, 5, 127]);
+
+        assert_eq!(set, NumberSet::Bits(1 | 1 << 5 | 1 << 127));
+        assert!(set.contains(127) && set.contains(0));
+        assert!(!set.contains(6));
+        assert!(!set.contains(128) && !set.contains(-1));
+    }
+
+    #[test]
+    fn large_numbers_fall_back() {
+        let set = NumberSet::new(&[3, 1000, -4]);
+
+        assert!(matches!(set, NumberSet::Hashed(_)));
+        assert!(set.contains(1000) && set.contains(-4) && set.contains(3));
+        assert!(!set.contains(4));
+    }
+}